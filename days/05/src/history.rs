@@ -0,0 +1,311 @@
+use std::io::{self, BufRead, Write};
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::{Crane, MoveInstructions};
+
+/// Execution history of a crane procedure.
+///
+/// The state of all stacks is recorded after every move, so stepping backwards or jumping to an
+/// arbitrary instruction is just an index into the recorded states.
+pub struct History {
+    /// The moves that were executed, in order.
+    moves: Vec<MoveInstructions>,
+
+    /// `states[0]` is the initial arrangement, `states[i]` the arrangement after the i-th move.
+    states: Vec<Vec<Vec<char>>>,
+
+    /// Index of the state that is currently being looked at.
+    position: usize,
+}
+
+impl History {
+    /// Run all moves on the stacks with the given crane and record every intermediate state.
+    pub fn record(stacks: &[Vec<char>], moves: &[MoveInstructions], crane: Crane) -> Self {
+        let mut states = Vec::with_capacity(moves.len() + 1);
+        let mut current = stacks.to_vec();
+        states.push(current.clone());
+
+        for mv in moves {
+            mv.apply(&mut current, crane);
+            states.push(current.clone());
+        }
+
+        History {
+            moves: moves.to_vec(),
+            states,
+            position: 0,
+        }
+    }
+
+    /// Number of recorded moves.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Number of moves that have been executed to reach the current state.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The stacks at the current position.
+    pub fn current(&self) -> &[Vec<char>] {
+        &self.states[self.position]
+    }
+
+    /// The move that produced the current state, or `None` for the initial state.
+    pub fn last_move(&self) -> Option<&MoveInstructions> {
+        self.position.checked_sub(1).map(|idx| &self.moves[idx])
+    }
+
+    /// The move that will be executed next, or `None` if all moves have been executed.
+    pub fn next_move(&self) -> Option<&MoveInstructions> {
+        self.moves.get(self.position)
+    }
+
+    /// The stacks after `n` moves have been executed.
+    pub fn state(&self, n: usize) -> Option<&[Vec<char>]> {
+        self.states.get(n).map(|s| &s[..])
+    }
+
+    /// Execute the next move. Returns false if there are no moves left.
+    pub fn step_forward(&mut self) -> bool {
+        self.jump(self.position + 1)
+    }
+
+    /// Undo the last move. Returns false if we are already at the initial state.
+    pub fn step_back(&mut self) -> bool {
+        match self.position.checked_sub(1) {
+            Some(n) => self.jump(n),
+            None => false,
+        }
+    }
+
+    /// Jump to the state after `n` moves. Returns false and stays put if `n` is out of range.
+    pub fn jump(&mut self, n: usize) -> bool {
+        if n < self.states.len() {
+            self.position = n;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Render the stacks as the ASCII diagram used by the puzzle.
+pub fn render(stacks: &[Vec<char>]) -> String {
+    render_frame(stacks, None)
+}
+
+/// Render the stacks, optionally with some crates hanging from the crane above one of the stacks.
+///
+/// The hanging crates are given bottom to top, just like the stacks.
+fn render_frame(stacks: &[Vec<char>], hanging: Option<(usize, &[char])>) -> String {
+    let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+    let total_height = match hanging {
+        Some((_, crates)) if !crates.is_empty() => height + crates.len() + 1,
+        _ => height,
+    };
+
+    let mut lines = Vec::with_capacity(total_height + 1);
+    for level in (0..total_height).rev() {
+        let line: String = (0..stacks.len())
+            .map(|i| {
+                let crate_at = match hanging {
+                    Some((col, crates)) if col == i && level > height => {
+                        crates.get(level - height - 1).copied()
+                    }
+                    _ => stacks[i].get(level).copied(),
+                };
+                match crate_at {
+                    Some(c) => format!("[{}] ", c),
+                    None => String::from("    "),
+                }
+            })
+            .collect();
+        lines.push(line.trim_end().to_string());
+    }
+
+    let numbers: String = (1..=stacks.len()).map(|i| format!(" {}  ", i)).collect();
+    lines.push(numbers.trim_end().to_string());
+
+    lines.join("\n")
+}
+
+/// Render every frame of a single move, starting from the stacks before the move.
+///
+/// Each lift of the crane shows the crates hanging above the source stack, then above the
+/// destination stack, and finally dropped onto it.
+fn move_frames(before: &[Vec<char>], mv: &MoveInstructions, crane: Crane) -> Vec<String> {
+    let lift = match crane {
+        Crane::CrateMover9000 => 1,
+        // A move of no crates has nothing to lift, and no frames.
+        Crane::CrateMover9001 => mv.num.max(1),
+    };
+
+    let mut stacks = before.to_vec();
+    let mut frames = Vec::new();
+    for _ in 0..mv.num / lift {
+        let idx = stacks[mv.from].len() - lift;
+        let crates: Vec<char> = stacks[mv.from].drain(idx..).collect();
+        frames.push(render_frame(&stacks, Some((mv.from, &crates))));
+        frames.push(render_frame(&stacks, Some((mv.to, &crates))));
+        stacks[mv.to].extend(crates);
+        frames.push(render(&stacks));
+    }
+
+    frames
+}
+
+/// Animate the whole history in the terminal, waiting `delay` between frames.
+pub fn animate(history: &History, crane: Crane, delay: Duration) -> Result<()> {
+    let mut stdout = io::stdout();
+
+    for n in 0..history.len() {
+        let before = history.state(n).unwrap();
+        let mv = &history.moves[n];
+        for frame in move_frames(before, mv, crane) {
+            // Clear the screen and move the cursor to the top left corner.
+            write!(stdout, "\x1b[2J\x1b[H")?;
+            writeln!(stdout, "Step {}/{}: {}", n + 1, history.len(), mv)?;
+            writeln!(stdout, "{}", frame)?;
+            stdout.flush()?;
+            sleep(delay);
+        }
+    }
+
+    Ok(())
+}
+
+/// Step through the history with commands read from stdin.
+///
+/// Supported commands are `n` (next), `b` (back), `g N` (go to step N) and `q` (quit).
+pub fn step_interactive(mut history: History) -> Result<()> {
+    let stdin = io::stdin();
+    print_position(&history);
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let mut parts = line.split_whitespace();
+        let moved = match parts.next() {
+            None | Some("n") => history.step_forward(),
+            Some("b") => history.step_back(),
+            Some("g") => match parts.next().map(str::parse) {
+                Some(Ok(n)) => history.jump(n),
+                _ => {
+                    println!("Usage: g <step>");
+                    continue;
+                }
+            },
+            Some("q") => break,
+            Some(x) => {
+                println!(
+                    "Unknown command \"{}\", expected one of n, b, g <step>, q",
+                    x
+                );
+                continue;
+            }
+        };
+
+        if !moved {
+            println!("Can't go there, there are {} steps", history.len());
+        }
+        print_position(&history);
+    }
+
+    Ok(())
+}
+
+fn print_position(history: &History) {
+    match history.last_move() {
+        Some(mv) => println!("Step {}/{}: {}", history.position(), history.len(), mv),
+        None => println!("Step 0/{}: initial state", history.len()),
+    }
+    println!("{}", render(history.current()));
+    if let Some(mv) = history.next_move() {
+        println!("Next: {}", mv);
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[allow(dead_code)]
+    fn example() -> (Vec<Vec<char>>, Vec<MoveInstructions>) {
+        let stacks = vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']];
+        let moves = [(1, 2, 1), (3, 1, 3), (2, 2, 1), (1, 1, 2)]
+            .into_iter()
+            .map(|(num, from, to)| MoveInstructions {
+                num,
+                from: from - 1,
+                to: to - 1,
+            })
+            .collect();
+
+        (stacks, moves)
+    }
+
+    #[test]
+    fn render_example() {
+        let (stacks, _) = example();
+        assert_eq!("    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3", render(&stacks),);
+    }
+
+    #[test]
+    fn step_forward_and_back() {
+        let (stacks, moves) = example();
+        let mut history = History::record(&stacks, &moves, Crane::CrateMover9000);
+
+        while history.step_forward() {}
+        assert_eq!(4, history.position());
+        let tops: String = history.current().iter().filter_map(|s| s.last()).collect();
+        assert_eq!("CMZ", tops);
+
+        while history.step_back() {}
+        assert_eq!(&stacks[..], history.current());
+    }
+
+    #[test]
+    fn jump_to_step() {
+        let (stacks, moves) = example();
+        let mut history = History::record(&stacks, &moves, Crane::CrateMover9001);
+
+        assert!(history.jump(2));
+        assert_eq!(
+            &[vec![], vec!['M', 'C'], vec!['P', 'Z', 'N', 'D']][..],
+            history.current()
+        );
+        assert!(!history.jump(5));
+        assert_eq!(2, history.position());
+    }
+
+    #[test]
+    fn move_frames_lift_one_crate_at_a_time() {
+        let (stacks, moves) = example();
+        let history = History::record(&stacks, &moves, Crane::CrateMover9000);
+        let before = history.state(1).unwrap();
+
+        let frames = move_frames(before, &moves[1], Crane::CrateMover9000);
+        assert_eq!(9, frames.len());
+        assert_eq!("[D]\n\n[N] [C]\n[Z] [M] [P]\n 1   2   3", frames[0]);
+
+        let frames = move_frames(before, &moves[1], Crane::CrateMover9001);
+        assert_eq!(3, frames.len());
+    }
+
+    #[test]
+    fn move_frames_without_crates() {
+        let (stacks, _) = example();
+        let mv = MoveInstructions {
+            num: 0,
+            from: 0,
+            to: 1,
+        };
+        for crane in [Crane::CrateMover9000, Crane::CrateMover9001] {
+            assert!(move_frames(&stacks, &mv, crane).is_empty());
+        }
+    }
+}
@@ -1,34 +1,103 @@
+use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::time::Duration;
 
 use anyhow::Result;
 use lazy_static::lazy_static;
 use regex::Regex;
 
-struct MoveInstructions {
+use history::History;
+
+mod history;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MoveInstructions {
     num: usize,
     from: usize,
     to: usize,
 }
 
+impl MoveInstructions {
+    /// Apply the move to the stacks, using the given crane model.
+    pub fn apply(&self, stacks: &mut [Vec<char>], crane: Crane) {
+        match crane {
+            Crane::CrateMover9000 => {
+                for _ in 0..self.num {
+                    match stacks[self.from].pop() {
+                        Some(val) => stacks[self.to].push(val),
+                        None => panic!("Tried to pop from empty stack {}.", self.from),
+                    }
+                }
+            }
+            Crane::CrateMover9001 => {
+                let idx = stacks[self.from].len() - self.num;
+                let mut elems: Vec<char> = stacks[self.from].drain(idx..).collect();
+                stacks[self.to].append(&mut elems);
+            }
+        }
+    }
+}
+
+impl Display for MoveInstructions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.num,
+            self.from + 1,
+            self.to + 1
+        )
+    }
+}
+
+/// The crane model determines whether crates are moved one at a time or all at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Crane {
+    CrateMover9000,
+    CrateMover9001,
+}
+
 fn main() -> Result<()> {
     let (stacks, moves) = parse_input()?;
-    problem_1(&stacks, &moves);
-    problem_2(&stacks, &moves);
+
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("--step") => {
+            let history = History::record(&stacks, &moves, crane_from_arg(args.next())?);
+            history::step_interactive(history)?;
+        }
+        Some("--animate") => {
+            let crane = crane_from_arg(args.next())?;
+            let delay = match args.next() {
+                Some(ms) => Duration::from_millis(ms.parse()?),
+                None => Duration::from_millis(200),
+            };
+            history::animate(&History::record(&stacks, &moves, crane), crane, delay)?;
+        }
+        Some(arg) => anyhow::bail!("Unknown argument \"{}\"", arg),
+        None => {
+            problem_1(&stacks, &moves);
+            problem_2(&stacks, &moves);
+        }
+    }
 
     Ok(())
 }
 
+fn crane_from_arg(arg: Option<String>) -> Result<Crane> {
+    match arg.as_deref() {
+        None | Some("9000") => Ok(Crane::CrateMover9000),
+        Some("9001") => Ok(Crane::CrateMover9001),
+        Some(x) => anyhow::bail!("Unknown crane model \"{}\", expected 9000 or 9001", x),
+    }
+}
+
 fn problem_1(stacks: &[Vec<char>], moves: &[MoveInstructions]) {
     let mut stacks: Vec<_> = stacks.to_vec();
 
     for mv in moves {
-        for _ in 0..mv.num {
-            match stacks[mv.from].pop() {
-                Some(val) => stacks[mv.to].push(val),
-                None => panic!("Tried to pop from empty stack {}.", mv.from),
-            }
-        }
+        mv.apply(&mut stacks, Crane::CrateMover9000);
     }
 
     for (i, stack) in stacks.iter().enumerate() {
@@ -43,12 +112,7 @@ fn problem_2(stacks: &[Vec<char>], moves: &[MoveInstructions]) {
     let mut stacks: Vec<_> = stacks.to_vec();
 
     for mv in moves {
-        let idx = stacks[mv.from].len() - mv.num;
-        let mut elems: Vec<char> = stacks[mv.from].iter().skip(idx).copied().collect();
-        stacks[mv.to].append(&mut elems);
-        for _ in 0..mv.num {
-            stacks[mv.from].pop();
-        }
+        mv.apply(&mut stacks, Crane::CrateMover9001);
     }
 
     for (i, stack) in stacks.iter().enumerate() {
@@ -0,0 +1,236 @@
+use std::io::{self, Read};
+
+use crate::marker::Window;
+
+/// Length of the start-of-packet marker.
+pub const PACKET_MARKER_LENGTH: usize = 4;
+
+/// Length of the start-of-message marker.
+pub const MESSAGE_MARKER_LENGTH: usize = 14;
+
+/// Marker found by a [`MarkerDetector`]. The offset is the absolute position of the first byte after
+/// the marker, counted from the very first byte fed to the detector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    StartOfPacket(usize),
+    StartOfMessage(usize),
+}
+
+/// Incremental marker detection over a stream that arrives in arbitrarily sized chunks.
+///
+/// Only a constant amount of state is kept between chunks, so the stream never has to be held in
+/// memory. Each kind of marker is reported once, the first time it occurs.
+#[derive(Clone, Debug)]
+pub struct MarkerDetector {
+    packet: Option<Window>,
+    message: Option<Window>,
+
+    /// Markers found before any byte was fed, which the next call to [`MarkerDetector::feed`]
+    /// reports.
+    pending: Vec<Event>,
+
+    /// Number of bytes fed so far.
+    offset: usize,
+}
+
+impl MarkerDetector {
+    pub fn new() -> Self {
+        Self::with_lengths(PACKET_MARKER_LENGTH, MESSAGE_MARKER_LENGTH)
+    }
+
+    /// Create a detector with custom marker lengths. Like with [`crate::marker::find_start`], a
+    /// marker of length 0 is found right at the start.
+    pub fn with_lengths(packet_length: usize, message_length: usize) -> Self {
+        let mut pending = Vec::new();
+        if packet_length == 0 {
+            pending.push(Event::StartOfPacket(0));
+        }
+        if message_length == 0 {
+            pending.push(Event::StartOfMessage(0));
+        }
+
+        MarkerDetector {
+            packet: (packet_length > 0).then(|| Window::new(packet_length)),
+            message: (message_length > 0).then(|| Window::new(message_length)),
+            pending,
+            offset: 0,
+        }
+    }
+
+    /// Number of bytes fed so far.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Whether both markers have been found and reported, so feeding more bytes is pointless.
+    pub fn is_done(&self) -> bool {
+        self.packet.is_none() && self.message.is_none() && self.pending.is_empty()
+    }
+
+    /// Feed the next chunk of the stream and return the markers that end inside it.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Event> {
+        let mut events = std::mem::take(&mut self.pending);
+
+        for byte in bytes {
+            if self.is_done() {
+                break;
+            }
+
+            if let Some(window) = &mut self.packet {
+                if window.push(self.offset, *byte).is_some() {
+                    events.push(Event::StartOfPacket(self.offset + 1));
+                    self.packet = None;
+                }
+            }
+            if let Some(window) = &mut self.message {
+                if window.push(self.offset, *byte).is_some() {
+                    events.push(Event::StartOfMessage(self.offset + 1));
+                    self.message = None;
+                }
+            }
+
+            self.offset += 1;
+        }
+
+        events
+    }
+
+    /// Read from `reader` until both markers are found or the stream ends, calling `on_event` as
+    /// soon as each marker is found.
+    pub fn read_from<R: Read>(
+        &mut self,
+        mut reader: R,
+        mut on_event: impl FnMut(Event),
+    ) -> io::Result<()> {
+        let mut buf = [0; 4096];
+
+        // Report the markers found before the first byte, even if the stream is empty.
+        self.feed(&[]).into_iter().for_each(&mut on_event);
+        while !self.is_done() {
+            let bytes_read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            self.feed(&buf[..bytes_read])
+                .into_iter()
+                .for_each(&mut on_event);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for MarkerDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::marker::EXAMPLES;
+
+    /// Reader which hands out at most `chunk_size` bytes per call.
+    #[allow(dead_code)]
+    struct Trickle<'a> {
+        data: &'a [u8],
+        chunk_size: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.chunk_size.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn feed_whole_stream() {
+        for (stream, packet, message) in EXAMPLES {
+            let mut detector = MarkerDetector::new();
+            assert_eq!(
+                vec![Event::StartOfPacket(packet), Event::StartOfMessage(message)],
+                detector.feed(stream.as_bytes()),
+            );
+            assert!(detector.is_done());
+        }
+    }
+
+    #[test]
+    fn feed_split_at_every_pair_of_boundaries() {
+        for (stream, packet, message) in EXAMPLES {
+            let stream = stream.as_bytes();
+            for i in 0..=stream.len() {
+                for j in i..=stream.len() {
+                    let mut detector = MarkerDetector::new();
+                    let mut events = detector.feed(&stream[..i]);
+                    events.extend(detector.feed(&stream[i..j]));
+                    events.extend(detector.feed(&stream[j..]));
+                    assert_eq!(
+                        vec![Event::StartOfPacket(packet), Event::StartOfMessage(message)],
+                        events,
+                        "split at {} and {}",
+                        i,
+                        j
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn read_from_every_chunk_size() {
+        for (stream, packet, message) in EXAMPLES {
+            for chunk_size in 1..=stream.len() {
+                let reader = Trickle {
+                    data: stream.as_bytes(),
+                    chunk_size,
+                };
+                let mut events = Vec::new();
+                MarkerDetector::new()
+                    .read_from(reader, |event| events.push(event))
+                    .unwrap();
+                assert_eq!(
+                    vec![Event::StartOfPacket(packet), Event::StartOfMessage(message)],
+                    events,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn feed_without_markers() {
+        let mut detector = MarkerDetector::new();
+        assert!(detector.feed(b"abab").is_empty());
+        assert!(detector.feed(b"abab").is_empty());
+        assert_eq!(8, detector.offset());
+        assert!(!detector.is_done());
+    }
+
+    #[test]
+    fn empty_markers() {
+        let mut detector = MarkerDetector::with_lengths(0, 3);
+        assert!(!detector.is_done());
+        assert_eq!(
+            vec![Event::StartOfPacket(0), Event::StartOfMessage(3)],
+            detector.feed(b"abc")
+        );
+        assert!(detector.is_done());
+
+        let mut events = Vec::new();
+        MarkerDetector::with_lengths(0, 0)
+            .read_from(io::empty(), |event| events.push(event))
+            .unwrap();
+        assert_eq!(
+            vec![Event::StartOfPacket(0), Event::StartOfMessage(0)],
+            events
+        );
+    }
+}
@@ -1,10 +1,20 @@
-use std::collections::HashMap;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read};
 
 use anyhow::Result;
 
+use detector::{Event, MarkerDetector};
+use marker::{find_start, markers, Matches};
+
+mod detector;
+mod marker;
+
 fn main() -> Result<()> {
+    // Pass "-" to detect markers on stdin as the bytes arrive.
+    if std::env::args().nth(1).as_deref() == Some("-") {
+        return detect_stdin();
+    }
+
     // let stream = parse_input("./example.txt")?;
     let stream = parse_input("./input.txt")?;
 
@@ -13,6 +23,9 @@ fn main() -> Result<()> {
     let message_start = find_start(&stream[..], 14);
     println!("Problem 2: {:?}", message_start);
 
+    let num_markers = markers(&stream[..], 4, Matches::NonOverlapping).count();
+    println!("Non-overlapping start-of-packet markers: {}", num_markers);
+
     Ok(())
 }
 
@@ -25,41 +38,13 @@ fn parse_input(path: &str) -> Result<Vec<u8>> {
     Ok(stream)
 }
 
-fn find_start(stream: &[u8], chunk_length: usize) -> Option<usize> {
-    let mut res: Option<usize> = None;
-    let mut idx: usize = 0;
-
-    // Create a map to quickly check if the chunk already contains the value. The value of the map
-    // is the offset of the byte.
-    let mut map: HashMap<&u8, usize> = HashMap::with_capacity(chunk_length);
+fn detect_stdin() -> Result<()> {
+    let mut detector = MarkerDetector::new();
+    detector.read_from(io::stdin().lock(), |event| match event {
+        Event::StartOfPacket(offset) => println!("Start of packet: {}", offset),
+        Event::StartOfMessage(offset) => println!("Start of message: {}", offset),
+    })?;
+    println!("Processed {} bytes from stdin", detector.offset());
 
-    'outer: loop {
-        if idx + chunk_length >= stream.len() {
-            break;
-        }
-
-        // Get a reference to the next 4 chunks of the stream.
-        let chunk = &stream[idx..idx + chunk_length];
-
-        // Loop through each byte and add it to the set. As soon as we get to a duplicate element,
-        // we can increment the index by at least that much and continue to the next iteration
-        // immediately.
-        map.clear();
-        for (i, byte) in chunk.iter().enumerate() {
-            // println!("set: {:?}, adding: {} offset: {}", map, byte, i);
-            if !map.contains_key(byte) {
-                map.insert(byte, i);
-            } else {
-                // println!("Found duplicate, jumping forward by {}", i);
-                idx += map.get(byte).unwrap() + 1;
-                continue 'outer;
-            }
-        }
-
-        // If we get here, then chunks must contain n unique bytes.
-        res = Some(idx + chunk_length);
-        break;
-    }
-
-    res
+    Ok(())
 }
@@ -0,0 +1,209 @@
+use std::ops::Range;
+
+/// Whether markers found by [`markers`] may share bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Matches {
+    /// Report every position at which a window of distinct bytes ends.
+    Overlapping,
+
+    /// Only start looking for the next marker after the end of the previous one.
+    NonOverlapping,
+}
+
+/// A run of `chunk_length` distinct bytes in a stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Marker<'a> {
+    /// Offsets of the marker in the stream. The end is the position the puzzle asks for.
+    pub range: Range<usize>,
+
+    /// The bytes making up the marker.
+    pub bytes: &'a [u8],
+}
+
+/// Sliding window which tracks the longest run of distinct bytes, capped at `chunk_length`.
+///
+/// For every byte we remember where we saw it last, and as soon as a byte repeats inside the
+/// current window, the window start jumps past the earlier occurrence, since no window containing
+/// both can be a marker.
+#[derive(Clone, Debug)]
+pub struct Window {
+    chunk_length: usize,
+
+    /// Offset at which each byte value was seen last.
+    last_seen: [Option<usize>; 256],
+
+    /// Offset of the first byte in the current window.
+    start: usize,
+}
+
+impl Window {
+    pub fn new(chunk_length: usize) -> Self {
+        assert!(chunk_length > 0, "markers must be at least one byte long");
+
+        Window {
+            chunk_length,
+            last_seen: [None; 256],
+            start: 0,
+        }
+    }
+
+    /// Add the byte at `offset` to the window. Offsets must be consecutive, starting at 0.
+    ///
+    /// Returns the range of the marker if the window now ends in `chunk_length` distinct bytes.
+    pub fn push(&mut self, offset: usize, byte: u8) -> Option<Range<usize>> {
+        if let Some(prev) = self.last_seen[byte as usize] {
+            if prev >= self.start {
+                self.start = prev + 1;
+            }
+        }
+        self.last_seen[byte as usize] = Some(offset);
+
+        let end = offset + 1;
+        if end - self.start > self.chunk_length {
+            self.start = end - self.chunk_length;
+        }
+
+        (end - self.start == self.chunk_length).then_some(self.start..end)
+    }
+
+    /// Forget the current window, so that the next marker starts after `offset`.
+    pub fn restart_after(&mut self, offset: usize) {
+        self.start = offset + 1;
+    }
+}
+
+/// Iterator over the markers in a stream, see [`markers`].
+pub struct Markers<'a> {
+    stream: &'a [u8],
+
+    /// `None` for empty markers, which aren't reported.
+    window: Option<Window>,
+    matches: Matches,
+    offset: usize,
+}
+
+impl<'a> Iterator for Markers<'a> {
+    type Item = Marker<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let window = self.window.as_mut()?;
+        while self.offset < self.stream.len() {
+            let offset = self.offset;
+            self.offset += 1;
+
+            if let Some(range) = window.push(offset, self.stream[offset]) {
+                if self.matches == Matches::NonOverlapping {
+                    window.restart_after(offset);
+                }
+
+                return Some(Marker {
+                    bytes: &self.stream[range.clone()],
+                    range,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+/// Iterate over every marker of `chunk_length` distinct bytes in the stream, in order of their end.
+/// There are no markers of length 0.
+pub fn markers(stream: &[u8], chunk_length: usize, matches: Matches) -> Markers<'_> {
+    Markers {
+        stream,
+        window: (chunk_length > 0).then(|| Window::new(chunk_length)),
+        matches,
+        offset: 0,
+    }
+}
+
+/// Find the end of the first window of `chunk_length` distinct bytes. An empty window is found
+/// right at the start.
+pub fn find_start(stream: &[u8], chunk_length: usize) -> Option<usize> {
+    if chunk_length == 0 {
+        return Some(0);
+    }
+
+    markers(stream, chunk_length, Matches::Overlapping)
+        .next()
+        .map(|marker| marker.range.end)
+}
+
+/// Example streams of the puzzle, with the ends of their first start-of-packet and
+/// start-of-message markers.
+#[cfg(test)]
+pub const EXAMPLES: [(&str, usize, usize); 5] = [
+    ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+    ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+    ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+    ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+    ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+];
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn find_start_examples() {
+        for (stream, packet, message) in EXAMPLES {
+            assert_eq!(Some(packet), find_start(stream.as_bytes(), 4), "{}", stream);
+            assert_eq!(
+                Some(message),
+                find_start(stream.as_bytes(), 14),
+                "{}",
+                stream
+            );
+        }
+    }
+
+    #[test]
+    fn find_start_without_marker() {
+        assert_eq!(None, find_start(b"aaaaaaaa", 2));
+        assert_eq!(None, find_start(b"", 4));
+    }
+
+    #[test]
+    fn find_start_marker_at_end_of_stream() {
+        assert_eq!(Some(4), find_start(b"abcd", 4));
+        assert_eq!(Some(5), find_start(b"aabcd", 4));
+    }
+
+    #[test]
+    fn empty_markers() {
+        assert_eq!(Some(0), find_start(b"abcd", 0));
+        assert_eq!(Some(0), find_start(b"", 0));
+        assert_eq!(0, markers(b"abcd", 0, Matches::Overlapping).count());
+    }
+
+    #[test]
+    fn markers_overlapping() {
+        let found: Vec<_> = markers(b"abcabb", 3, Matches::Overlapping).collect();
+        assert_eq!(
+            vec![
+                Marker {
+                    range: 0..3,
+                    bytes: b"abc"
+                },
+                Marker {
+                    range: 1..4,
+                    bytes: b"bca"
+                },
+                Marker {
+                    range: 2..5,
+                    bytes: b"cab"
+                },
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn markers_non_overlapping() {
+        let found: Vec<_> = markers(b"abcabcab", 3, Matches::NonOverlapping)
+            .map(|marker| marker.range)
+            .collect();
+        assert_eq!(vec![0..3, 3..6], found);
+    }
+}
@@ -0,0 +1,322 @@
+use std::fmt::Display;
+
+use crate::tree::{FsTree, NodeId, TreeError};
+
+/// Errors while replaying a terminal log. Line numbers start at 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// A command other than `cd` or `ls`.
+    UnknownCommand { line: usize, command: String },
+
+    /// A command or listing entry which is missing a part, such as `cd` without a directory.
+    MissingArgument { line: usize },
+
+    /// A command which doesn't take arguments, such as `ls`, given some anyway.
+    UnexpectedArguments {
+        line: usize,
+        command: String,
+        arguments: String,
+    },
+
+    /// A listing entry whose size is not a number.
+    InvalidSize { line: usize, size: String },
+
+    /// Trying to `cd` into a file, or list a directory where a file was seen before.
+    NotADirectory { line: usize, path: String },
+
+    /// A file listed where a directory was seen before.
+    NotAFile { line: usize, path: String },
+
+    /// A file listed again with a different size.
+    ConflictingSize {
+        line: usize,
+        path: String,
+        size: usize,
+        listed: usize,
+    },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnknownCommand { line, command } => {
+                write!(f, "line {}: unknown command \"{}\"", line, command)
+            }
+            ParseError::MissingArgument { line } => write!(f, "line {}: missing argument", line),
+            ParseError::UnexpectedArguments {
+                line,
+                command,
+                arguments,
+            } => write!(
+                f,
+                "line {}: {} takes no arguments, got \"{}\"",
+                line, command, arguments
+            ),
+            ParseError::InvalidSize { line, size } => {
+                write!(f, "line {}: invalid file size \"{}\"", line, size)
+            }
+            ParseError::NotADirectory { line, path } => {
+                write!(f, "line {}: {} is not a directory", line, path)
+            }
+            ParseError::NotAFile { line, path } => {
+                write!(f, "line {}: {} is not a file", line, path)
+            }
+            ParseError::ConflictingSize {
+                line,
+                path,
+                size,
+                listed,
+            } => write!(
+                f,
+                "line {}: {} is listed with size {}, but had size {} before",
+                line, path, listed, size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Replay the terminal log and reconstruct the filesystem it explored.
+///
+/// Listing the same directory more than once records every entry only once. `cd` accepts
+/// absolute and relative paths with any number of segments, including `.` and `..`. Directories
+/// which are entered without having been listed are created on the fly.
+pub fn parse_log(log: &str) -> Result<FsTree, ParseError> {
+    let mut tree = FsTree::new();
+    let mut pwd = tree.root();
+
+    for (idx, line) in log.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim_end();
+        if line.trim_start().is_empty() {
+            continue;
+        }
+
+        if let Some(command) = line.strip_prefix('$') {
+            // Only `cd` takes an argument, which may contain spaces.
+            let command = command.trim();
+            let (name, args) = match command.split_once(' ') {
+                Some((name, args)) => (name, args.trim()),
+                None => (command, ""),
+            };
+            match (name, args) {
+                ("" | "cd", "") => return Err(ParseError::MissingArgument { line: line_no }),
+                ("cd", target) => pwd = change_dir(&mut tree, pwd, target, line_no)?,
+                ("ls", "") => (),
+                ("ls", args) => {
+                    return Err(ParseError::UnexpectedArguments {
+                        line: line_no,
+                        command: String::from(name),
+                        arguments: String::from(args),
+                    })
+                }
+                (command, _) => {
+                    return Err(ParseError::UnknownCommand {
+                        line: line_no,
+                        command: String::from(command),
+                    })
+                }
+            }
+            continue;
+        }
+
+        // Listing entries are a size or `dir`, followed by the name, which may contain spaces.
+        let (size, name) = match line.split_once(' ') {
+            Some((size, name)) if !name.is_empty() => (size, name),
+            _ => return Err(ParseError::MissingArgument { line: line_no }),
+        };
+        if size == "dir" {
+            tree.add_dir(pwd, name)
+                .map_err(|err| tree_error(line_no, err))?;
+        } else {
+            let Ok(size) = size.parse() else {
+                return Err(ParseError::InvalidSize {
+                    line: line_no,
+                    size: String::from(size),
+                });
+            };
+            tree.add_file(pwd, name, size)
+                .map_err(|err| tree_error(line_no, err))?;
+        }
+    }
+
+    Ok(tree)
+}
+
+/// Attach the line of the log to an error of the tree.
+fn tree_error(line: usize, err: TreeError) -> ParseError {
+    match err {
+        TreeError::NotADirectory { path } => ParseError::NotADirectory { line, path },
+        TreeError::NotAFile { path } => ParseError::NotAFile { line, path },
+        TreeError::ConflictingSize { path, size, listed } => ParseError::ConflictingSize {
+            line,
+            path,
+            size,
+            listed,
+        },
+    }
+}
+
+/// Resolve the target of a `cd` relative to `pwd`, creating missing directories along the way.
+fn change_dir(
+    tree: &mut FsTree,
+    pwd: NodeId,
+    target: &str,
+    line: usize,
+) -> Result<NodeId, ParseError> {
+    let mut dir = if target.starts_with('/') {
+        tree.root()
+    } else {
+        pwd
+    };
+
+    for segment in target.split('/') {
+        dir = match segment {
+            "" | "." => dir,
+            // Going up from the root stays at the root, just like in a shell.
+            ".." => tree.node(dir).parent.unwrap_or(dir),
+            name => tree
+                .add_dir(dir, name)
+                .map_err(|err| tree_error(line, err))?,
+        };
+    }
+
+    Ok(dir)
+}
+
+/// The terminal log from the puzzle description.
+#[cfg(test)]
+pub const EXAMPLE: &str = "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k
+";
+
+/// The filesystem from the puzzle description.
+#[cfg(test)]
+pub fn example() -> FsTree {
+    parse_log(EXAMPLE).unwrap()
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn parse_log_example() {
+        let tree = parse_log(EXAMPLE).unwrap();
+        assert_eq!(Some(584), tree.size_of("/a/e"));
+        assert_eq!(Some(94853), tree.size_of("/a"));
+        assert_eq!(Some(24933642), tree.size_of("/d"));
+        assert_eq!(Some(48381165), tree.size_of("/"));
+    }
+
+    #[test]
+    fn parse_log_repeated_ls() {
+        let log = format!("{}$ cd /a\n$ ls\ndir e\n29116 f\n$ ls\n29116 f\n", EXAMPLE);
+        let tree = parse_log(&log).unwrap();
+        assert_eq!(Some(94853), tree.size_of("/a"));
+        assert_eq!(4, tree.children("/a").unwrap().len());
+    }
+
+    #[test]
+    fn parse_log_multi_segment_cd() {
+        let log =
+            "$ cd /x/y\n$ ls\n10 f\n$ cd ../../z/./w\n$ ls\n5 g\n$ cd /x/y/../..\n$ ls\n1 h\n";
+        let tree = parse_log(log).unwrap();
+        assert_eq!(Some(10), tree.size_of("/x/y/f"));
+        assert_eq!(Some(5), tree.size_of("/z/w"));
+        assert_eq!(Some(16), tree.size_of("/"));
+        assert_eq!("/z/w/g", tree.path(tree.lookup("/z/w/g").unwrap()));
+    }
+
+    #[test]
+    fn parse_log_errors() {
+        assert_eq!(
+            Err(ParseError::UnknownCommand {
+                line: 2,
+                command: String::from("rm")
+            }),
+            parse_log("$ cd /\n$ rm -rf a\n").map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseError::InvalidSize {
+                line: 2,
+                size: String::from("big")
+            }),
+            parse_log("$ ls\nbig file\n").map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseError::NotADirectory {
+                line: 3,
+                path: String::from("/f")
+            }),
+            parse_log("$ ls\n1 f\n$ cd f\n").map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseError::NotAFile {
+                line: 3,
+                path: String::from("/a")
+            }),
+            parse_log("$ ls\ndir a\n1 a\n").map(|_| ())
+        );
+    }
+
+    #[test]
+    fn parse_log_entry_errors() {
+        assert_eq!(
+            Err(ParseError::UnexpectedArguments {
+                line: 1,
+                command: String::from("ls"),
+                arguments: String::from("foo")
+            }),
+            parse_log("$ ls foo\n").map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseError::ConflictingSize {
+                line: 3,
+                path: String::from("/f"),
+                size: 1,
+                listed: 2
+            }),
+            parse_log("$ ls\n1 f\n2 f\n").map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseError::MissingArgument { line: 2 }),
+            parse_log("$ ls\n123\n").map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseError::MissingArgument { line: 1 }),
+            parse_log("$ cd\n").map(|_| ())
+        );
+    }
+
+    #[test]
+    fn parse_log_names_with_spaces() {
+        let log = "$ ls\n10 my file.txt\ndir my dir\n$ cd my dir\n$ ls\n5 x y z\n";
+        let tree = parse_log(log).unwrap();
+        assert_eq!(Some(10), tree.size_of("/my file.txt"));
+        assert_eq!(Some(5), tree.size_of("/my dir/x y z"));
+    }
+}
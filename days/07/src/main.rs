@@ -1,84 +1,109 @@
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
-};
-
 use anyhow::Result;
-use itertools::Itertools;
+
+use log::parse_log;
+use plan::Planner;
+use render::{render_du, render_tree, to_json, RenderOptions};
+use tree::FsTree;
+
+mod log;
+mod plan;
+mod render;
+mod tree;
 
 fn main() -> Result<()> {
-    let sizes = parse_input("input.txt")?;
-    let sum: usize = sizes.values().filter(|s| **s <= 100000).sum();
-    println!("Problem 1: {}", sum);
+    let tree = parse_input("input.txt")?;
 
-    // Problem 2
-    const FS_SIZE: usize = 70000000;
-    const UPDATE_SIZE: usize = 30000000;
-    let used_size = sizes["/"];
-    let free_size = dbg!(FS_SIZE - used_size);
-    let missing_size = dbg!(UPDATE_SIZE - free_size);
-    let mut sizes_2: Vec<_> = sizes.values().filter(|s| **s >= missing_size).collect();
-    sizes_2.sort();
-    println!(
-        "Problem 2: {} {}",
-        sizes_2.first().unwrap(),
-        sizes_2.last().unwrap()
-    );
+    let mut args = std::env::args().skip(1);
+    let mut options = RenderOptions::default();
+    let mut planner = Planner::new(70000000, 30000000);
+    let mut output = None;
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tree" | "--du" | "--json" => output = Some(arg),
+            "--depth" => options.max_depth = Some(parse_value(args.next(), &arg)?),
+            "--min-size" => options.min_size = parse_value(args.next(), &arg)?,
+            "--capacity" => planner.capacity = parse_value(args.next(), &arg)?,
+            "--required-free" => planner.required_free = parse_value(args.next(), &arg)?,
+            x if x.starts_with("--") => anyhow::bail!("Unknown option {}", x),
+            // Any other argument is a path to inspect.
+            _ => path = Some(arg),
+        }
+    }
+
+    match (output.as_deref(), path) {
+        (Some("--tree"), _) => println!("{}", render_tree(&tree, options)),
+        (Some("--du"), _) => println!("{}", render_du(&tree, options)),
+        (Some(_), _) => println!("{}", to_json(&tree, options)?),
+        (None, Some(path)) => inspect(&tree, &path)?,
+        (None, None) => {
+            println!("Reconstructed {} files and directories", tree.len());
+            problem_1(&tree);
+            problem_2(&tree, planner);
+        }
+    }
 
     Ok(())
 }
 
-fn parse_input(path: &str) -> Result<HashMap<String, usize>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-
-    // Stack of directories, representing current working dir.
-    let mut pwd: Vec<String> = Vec::new();
-    let mut sizes: HashMap<String, usize> = HashMap::new();
-
-    for s in reader.lines() {
-        let line = s.unwrap();
-        let tokens: Vec<&str> = line.split_whitespace().collect();
-
-        if tokens[0] == "$" {
-            // If the line is a command.
-            match tokens[1] {
-                "cd" => {
-                    let dirname = tokens[2];
-                    match dirname {
-                        ".." => {
-                            // println!("Going up");
-                            pwd.pop();
-                        }
-                        "/" => {
-                            // println!("Going to root");
-                            pwd.clear();
-                            pwd.push(String::from("/"));
-                        }
-                        x => {
-                            // println!("Going into {}", x);
-                            pwd.push(String::from(x));
-                        }
-                    }
-                }
-                "ls" => (),
-                _ => panic!("Shouldn't happen"),
-            }
-        } else {
-            // Is a file system item.
-            if tokens[0] != "dir" {
-                let size: usize = tokens[0].parse().unwrap();
-                let mut acc: Vec<&String> = Vec::with_capacity(pwd.len());
-                for p in &pwd {
-                    acc.push(p);
-                    let full_path = acc.iter().join("/");
-                    let entry = sizes.entry(full_path).or_default();
-                    *entry += size;
-                }
+fn parse_value(value: Option<String>, option: &str) -> Result<usize> {
+    match value {
+        Some(value) => Ok(value.parse()?),
+        None => anyhow::bail!("Missing value for {}", option),
+    }
+}
+
+fn dir_sizes(tree: &FsTree) -> Vec<usize> {
+    let sizes = tree.sizes();
+    tree.walk()
+        .filter(|entry| entry.node.is_dir())
+        .map(|entry| sizes[entry.id])
+        .collect()
+}
+
+fn problem_1(tree: &FsTree) {
+    let sum: usize = dir_sizes(tree).iter().filter(|s| **s <= 100000).sum();
+    println!("Problem 1: {}", sum);
+}
+
+fn problem_2(tree: &FsTree, planner: Planner) {
+    match planner.missing(tree) {
+        Some(missing) => println!("Missing: {}", missing),
+        None => println!("Missing: more than fits a usize"),
+    }
+
+    match planner.smallest_dir(tree) {
+        Some(dir) => println!("Problem 2: {} ({})", dir.size, dir.path),
+        None => println!("Problem 2: no directory is large enough"),
+    }
+
+    match planner.smallest_set(tree) {
+        Some(dirs) => {
+            let total: usize = dirs.iter().map(|dir| dir.size).sum();
+            println!("Smallest set of directories frees {}:", total);
+            for dir in dirs {
+                println!("  {} {}", dir.size, dir.path);
             }
         }
+        None => println!("Not even deleting everything frees enough space"),
     }
+}
+
+fn inspect(tree: &FsTree, path: &str) -> Result<()> {
+    let Some(size) = tree.size_of(path) else {
+        anyhow::bail!("No such file or directory: {}", path);
+    };
+    println!("{} {}", path, size);
+
+    for &id in tree.children(path).unwrap_or_default() {
+        let node = tree.node(id);
+        let kind = if node.is_dir() { "dir" } else { "file" };
+        println!("  {} {} {}", kind, tree.size(id), tree.path(id));
+    }
+
+    Ok(())
+}
 
-    Ok(sizes)
+fn parse_input(path: &str) -> Result<FsTree> {
+    Ok(parse_log(&std::fs::read_to_string(path)?)?)
 }
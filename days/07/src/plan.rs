@@ -0,0 +1,379 @@
+use std::collections::HashSet;
+
+use crate::tree::{FsTree, NodeId};
+
+/// A directory which could be deleted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub id: NodeId,
+    pub path: String,
+    pub size: usize,
+}
+
+/// Answers how to free up enough space on a disk of a given capacity.
+#[derive(Clone, Copy, Debug)]
+pub struct Planner {
+    /// Total size of the disk.
+    pub capacity: usize,
+
+    /// Space that has to be free after the cleanup.
+    pub required_free: usize,
+}
+
+impl Planner {
+    pub fn new(capacity: usize, required_free: usize) -> Self {
+        Planner {
+            capacity,
+            required_free,
+        }
+    }
+
+    /// Space which still has to be freed, 0 if there already is enough free space, or `None` if
+    /// the used and required space together don't even fit a `usize`.
+    pub fn missing(&self, tree: &FsTree) -> Option<usize> {
+        let needed = tree.size(tree.root()).checked_add(self.required_free)?;
+        Some(needed.saturating_sub(self.capacity))
+    }
+
+    /// The smallest directory whose deletion frees enough space.
+    pub fn smallest_dir(&self, tree: &FsTree) -> Option<Candidate> {
+        let missing = self.missing(tree)?;
+        let sizes = tree.sizes();
+
+        tree.walk()
+            .filter(|entry| entry.node.is_dir() && sizes[entry.id] >= missing)
+            .min_by_key(|entry| sizes[entry.id])
+            .map(|entry| candidate(tree, &sizes, entry.id))
+    }
+
+    /// The set of directories, none inside another, which together free enough space while
+    /// deleting as little as possible.
+    ///
+    /// This is a branch and bound search over the directories in pre-order: at every directory we
+    /// either delete it and skip everything inside it, or keep it and move on to the next one.
+    /// Deleting a directory always frees at least as much as deleting anything inside it, so the
+    /// most that can still be freed from some point on is the total of the outermost directories
+    /// left, and branches which can't reach the missing space are cut off. So are branches which
+    /// already free more than the best set found so far, and branches reaching a directory with a
+    /// total which was already tried there. The search stops as soon as it frees the missing space
+    /// rounded up to the greatest common divisor of the sizes, since nothing can do better. Memory
+    /// grows with the number of branches tried, not with the sizes involved.
+    pub fn smallest_set(&self, tree: &FsTree) -> Option<Vec<Candidate>> {
+        let missing = self.missing(tree)?;
+        if missing == 0 {
+            return Some(Vec::new());
+        }
+        let sizes = tree.sizes();
+        if missing > sizes[tree.root()] {
+            return None;
+        }
+
+        let dirs: Vec<_> = tree
+            .walk()
+            .filter(|entry| entry.node.is_dir())
+            .map(|entry| (entry.id, entry.depth))
+            .collect();
+
+        // Index of the first directory after the subtree of each directory.
+        let mut ends = vec![dirs.len(); dirs.len()];
+        let mut open: Vec<usize> = Vec::new();
+        for (idx, &(_, depth)) in dirs.iter().enumerate() {
+            while let Some(&prev) = open.last() {
+                if dirs[prev].1 < depth {
+                    break;
+                }
+                ends[prev] = idx;
+                open.pop();
+            }
+            open.push(idx);
+        }
+
+        // Most that can be freed by deleting directories from each index on.
+        let mut reach = vec![0; dirs.len() + 1];
+        for idx in (0..dirs.len()).rev() {
+            reach[idx] = sizes[dirs[idx].0] + reach[ends[idx]];
+        }
+
+        let dirs_sizes: Vec<_> = dirs.iter().map(|&(id, _)| sizes[id]).collect();
+        let step = dirs_sizes.iter().fold(0, |a, &b| gcd(a, b)).max(1);
+        let mut search = Search {
+            dirs: dirs_sizes,
+            ends,
+            reach,
+            missing,
+            target: missing.div_ceil(step) * step,
+            tried: HashSet::new(),
+            current: Vec::new(),
+            best: None,
+        };
+        search.run(0, 0);
+
+        let (_, chosen) = search.best?;
+        let mut chosen: Vec<_> = chosen
+            .into_iter()
+            .map(|idx| candidate(tree, &sizes, dirs[idx].0))
+            .collect();
+        chosen.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Some(chosen)
+    }
+}
+
+/// State of the search in [`Planner::smallest_set`]. Directories are referred to by their index in
+/// pre-order.
+struct Search {
+    /// Size of each directory.
+    dirs: Vec<usize>,
+
+    /// Index of the first directory after the subtree of each directory.
+    ends: Vec<usize>,
+
+    /// Most that can be freed by deleting directories from each index on.
+    reach: Vec<usize>,
+
+    missing: usize,
+
+    /// Smallest total any set can free which is enough.
+    target: usize,
+
+    /// Directories and totals so far which were already searched from.
+    tried: HashSet<(usize, usize)>,
+
+    /// Directories deleted on the current branch.
+    current: Vec<usize>,
+
+    /// Smallest total found so far which is enough, with its directories.
+    best: Option<(usize, Vec<usize>)>,
+}
+
+impl Search {
+    fn run(&mut self, idx: usize, total: usize) {
+        if self
+            .best
+            .as_ref()
+            .is_some_and(|(best, _)| *best == self.target)
+        {
+            return;
+        }
+        if total >= self.missing {
+            if self.best.as_ref().is_none_or(|(best, _)| total < *best) {
+                self.best = Some((total, self.current.clone()));
+            }
+            return;
+        }
+        if idx == self.dirs.len() || total + self.reach[idx] < self.missing {
+            return;
+        }
+        // Every set on this branch frees more than the best one.
+        if self.best.as_ref().is_some_and(|(best, _)| total >= *best) {
+            return;
+        }
+        // The same total from here on leads to the same sets, which were no better than the best.
+        if !self.tried.insert((idx, total)) {
+            return;
+        }
+
+        self.current.push(idx);
+        self.run(self.ends[idx], total + self.dirs[idx]);
+        self.current.pop();
+
+        self.run(idx + 1, total);
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn candidate(tree: &FsTree, sizes: &[usize], id: NodeId) -> Candidate {
+    Candidate {
+        id,
+        path: tree.path(id),
+        size: sizes[id],
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::log::example;
+
+    #[test]
+    fn smallest_dir_example() {
+        let planner = Planner::new(70000000, 30000000);
+        let tree = example();
+        assert_eq!(Some(8381165), planner.missing(&tree));
+
+        let dir = planner.smallest_dir(&tree).unwrap();
+        assert_eq!("/d", dir.path);
+        assert_eq!(24933642, dir.size);
+    }
+
+    #[test]
+    fn smallest_set_combines_directories() {
+        let mut tree = FsTree::new();
+        let root = tree.root();
+        let big = tree.add_dir(root, "big").unwrap();
+        let x = tree.add_dir(big, "x").unwrap();
+        tree.add_file(x, "f", 60).unwrap();
+        let y = tree.add_dir(big, "y").unwrap();
+        tree.add_file(y, "f", 50).unwrap();
+        tree.add_file(big, "f", 90).unwrap();
+        let z = tree.add_dir(root, "z").unwrap();
+        tree.add_file(z, "f", 45).unwrap();
+
+        // 245 used, so 105 have to be freed.
+        let planner = Planner::new(300, 160);
+        assert_eq!(Some(105), planner.missing(&tree));
+        assert_eq!("/big", planner.smallest_dir(&tree).unwrap().path);
+
+        let paths: Vec<_> = planner
+            .smallest_set(&tree)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.path)
+            .collect();
+        assert_eq!(vec!["/big/x", "/z"], paths);
+    }
+
+    #[test]
+    fn nothing_to_free() {
+        let planner = Planner::new(100000000, 30000000);
+        let tree = example();
+        assert_eq!(Some(0), planner.missing(&tree));
+        assert_eq!(Some(vec![]), planner.smallest_set(&tree));
+    }
+
+    #[test]
+    fn not_enough_space_even_when_deleting_everything() {
+        let planner = Planner::new(10, 30000000);
+        assert_eq!(None, planner.smallest_dir(&example()));
+        assert_eq!(None, planner.smallest_set(&example()));
+    }
+
+    /// Totals of all sets of non-nested directories inside `dir`, by trying all of them.
+    #[allow(dead_code)]
+    fn brute_force(tree: &FsTree, sizes: &[usize], dir: NodeId) -> Vec<usize> {
+        let mut totals = vec![0];
+        if let crate::tree::NodeKind::Dir { children } = &tree.node(dir).kind {
+            for &child in children {
+                if tree.node(child).is_dir() {
+                    let child_totals = brute_force(tree, sizes, child);
+                    totals = totals
+                        .iter()
+                        .flat_map(|t1| child_totals.iter().map(move |t2| t1 + t2))
+                        .collect();
+                }
+            }
+        }
+        totals.push(sizes[dir]);
+        totals
+    }
+
+    #[test]
+    fn smallest_set_matches_brute_force() {
+        let mut seed: u64 = 7;
+        let mut random = move |max: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) % max
+        };
+
+        for _ in 0..200 {
+            let mut tree = FsTree::new();
+            let mut dirs = vec![tree.root()];
+            for i in 0..random(10) {
+                let parent = dirs[random(dirs.len() as u64) as usize];
+                dirs.push(tree.add_dir(parent, &format!("d{}", i)).unwrap());
+            }
+            for i in 0..random(15) {
+                let parent = dirs[random(dirs.len() as u64) as usize];
+                tree.add_file(parent, &format!("f{}", i), random(100) as usize)
+                    .unwrap();
+            }
+
+            let sizes = tree.sizes();
+            let planner = Planner::new(
+                sizes[tree.root()],
+                random(sizes[tree.root()] as u64 + 10) as usize,
+            );
+            let missing = planner.missing(&tree).unwrap();
+            let expected = brute_force(&tree, &sizes, tree.root())
+                .into_iter()
+                .filter(|&total| total >= missing)
+                .min();
+
+            let set = planner.smallest_set(&tree);
+            let total = set
+                .as_ref()
+                .map(|dirs| dirs.iter().map(|dir| dir.size).sum());
+            assert_eq!(expected, total);
+
+            // None of the directories may be inside another one.
+            for dir in set.iter().flatten() {
+                for other in set.iter().flatten() {
+                    assert!(
+                        dir.id == other.id || !other.path.starts_with(&format!("{}/", dir.path))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn huge_required_free() {
+        let tree = example();
+        let planner = Planner::new(1000, 100000000000);
+        assert_eq!(None, planner.smallest_dir(&tree));
+        assert_eq!(None, planner.smallest_set(&tree));
+
+        // The used and required space together overflow.
+        let planner = Planner::new(usize::MAX, usize::MAX);
+        assert_eq!(None, planner.missing(&tree));
+        assert_eq!(None, planner.smallest_dir(&tree));
+        assert_eq!(None, planner.smallest_set(&tree));
+
+        // Exactly everything, which is only possible by deleting the root.
+        let planner = Planner::new(48381165, 48381165);
+        let set = planner.smallest_set(&tree).unwrap();
+        assert_eq!(
+            vec!["/"],
+            set.iter().map(|c| c.path.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn smallest_set_without_exact_total() {
+        // Every size is even, so no set frees exactly the odd missing space.
+        let mut tree = FsTree::new();
+        let mut dir = tree.root();
+        for i in 0..20 {
+            let child = tree.add_dir(dir, &format!("d{}", i)).unwrap();
+            tree.add_file(dir, &format!("f{}", i), 2 * (i + 1)).unwrap();
+            tree.add_file(child, "g", 1000).unwrap();
+            if i % 2 == 0 {
+                dir = child;
+            }
+        }
+
+        let used = tree.size(tree.root());
+        let planner = Planner::new(used, 10001);
+        let sizes = tree.sizes();
+        let expected = brute_force(&tree, &sizes, tree.root())
+            .into_iter()
+            .filter(|&total| total >= 10001)
+            .min();
+        let total: usize = planner
+            .smallest_set(&tree)
+            .unwrap()
+            .iter()
+            .map(|c| c.size)
+            .sum();
+        assert_eq!(expected, Some(total));
+        assert_eq!(0, total % 2);
+    }
+}
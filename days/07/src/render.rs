@@ -0,0 +1,253 @@
+use serde::Serialize;
+
+use crate::tree::{FsTree, NodeId, NodeKind};
+
+/// Which nodes to include when rendering a [`FsTree`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderOptions {
+    /// Skip everything more than this many directories below the root.
+    pub max_depth: Option<usize>,
+
+    /// Skip files and directories smaller than this, including everything inside them.
+    pub min_size: usize,
+}
+
+impl RenderOptions {
+    fn includes(&self, depth: usize, size: usize) -> bool {
+        self.max_depth.is_none_or(|max| depth <= max) && size >= self.min_size
+    }
+}
+
+/// Render the tree like `tree -s`, with the size of every node in front of its name.
+pub fn render_tree(tree: &FsTree, options: RenderOptions) -> String {
+    let sizes = tree.sizes();
+    let mut lines = Vec::new();
+    if options.includes(0, sizes[tree.root()]) {
+        lines.push(format!("[{:>11}]  /", sizes[tree.root()]));
+        render_children(tree, &sizes, options, tree.root(), 1, "", &mut lines);
+    }
+
+    lines.join("\n")
+}
+
+fn render_children(
+    tree: &FsTree,
+    sizes: &[usize],
+    options: RenderOptions,
+    dir: NodeId,
+    depth: usize,
+    prefix: &str,
+    lines: &mut Vec<String>,
+) {
+    let NodeKind::Dir { children } = &tree.node(dir).kind else {
+        return;
+    };
+    let children: Vec<_> = children
+        .iter()
+        .copied()
+        .filter(|&id| options.includes(depth, sizes[id]))
+        .collect();
+
+    for (idx, &id) in children.iter().enumerate() {
+        let last = idx + 1 == children.len();
+        let (branch, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        lines.push(format!(
+            "{}{}[{:>11}]  {}",
+            prefix,
+            branch,
+            sizes[id],
+            tree.node(id).name
+        ));
+
+        let prefix = format!("{}{}", prefix, indent);
+        render_children(tree, sizes, options, id, depth + 1, &prefix, lines);
+    }
+}
+
+/// Render the directories like `du -h --apparent-size`, largest first.
+pub fn render_du(tree: &FsTree, options: RenderOptions) -> String {
+    let sizes = tree.sizes();
+    let mut dirs: Vec<_> = tree
+        .walk()
+        .filter(|entry| entry.node.is_dir() && options.includes(entry.depth, sizes[entry.id]))
+        .map(|entry| (sizes[entry.id], tree.path(entry.id)))
+        .collect();
+    dirs.sort_by(|(s1, p1), (s2, p2)| s2.cmp(s1).then_with(|| p1.cmp(p2)));
+
+    dirs.into_iter()
+        .map(|(size, path)| format!("{}\t{}", human_size(size), path))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format a size in bytes the way `du -h` does, rounding up to the next unit of 1024.
+pub fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if value < 10.0 {
+        let value = (value * 10.0).ceil() / 10.0;
+        if value < 10.0 {
+            return format!("{:.1}{}", value, UNITS[unit]);
+        }
+    }
+    format!("{}{}", value.ceil(), UNITS[unit])
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum JsonKind {
+    Dir,
+    File,
+}
+
+/// Nested view of the tree, used for the JSON export.
+#[derive(Debug, Serialize)]
+struct JsonNode<'a> {
+    name: &'a str,
+    kind: JsonKind,
+    size: usize,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    children: Option<Vec<JsonNode<'a>>>,
+}
+
+fn json_node<'a>(
+    tree: &'a FsTree,
+    sizes: &[usize],
+    options: RenderOptions,
+    id: NodeId,
+    depth: usize,
+) -> JsonNode<'a> {
+    let node = tree.node(id);
+    let (kind, children) = match &node.kind {
+        NodeKind::Dir { children } => (
+            JsonKind::Dir,
+            Some(
+                children
+                    .iter()
+                    .copied()
+                    .filter(|&child| options.includes(depth + 1, sizes[child]))
+                    .map(|child| json_node(tree, sizes, options, child, depth + 1))
+                    .collect(),
+            ),
+        ),
+        NodeKind::File { .. } => (JsonKind::File, None),
+    };
+
+    JsonNode {
+        name: &node.name,
+        kind,
+        size: sizes[id],
+        children,
+    }
+}
+
+/// Serialise the tree as nested JSON objects, with `name`, `kind`, `size` and `children` fields.
+/// If the options filter out the root, like [`render_tree`] this renders nothing, as `null`.
+pub fn to_json(tree: &FsTree, options: RenderOptions) -> serde_json::Result<String> {
+    let sizes = tree.sizes();
+    let root = options
+        .includes(0, sizes[tree.root()])
+        .then(|| json_node(tree, &sizes, options, tree.root(), 0));
+    serde_json::to_string_pretty(&root)
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[allow(dead_code)]
+    fn example() -> FsTree {
+        let mut tree = FsTree::new();
+        let root = tree.root();
+        let a = tree.add_dir(root, "a").unwrap();
+        tree.add_file(root, "b.txt", 14848514).unwrap();
+        let e = tree.add_dir(a, "e").unwrap();
+        tree.add_file(a, "f", 29116).unwrap();
+        tree.add_file(e, "i", 584).unwrap();
+
+        tree
+    }
+
+    #[test]
+    fn render_tree_example() {
+        let expected = [
+            "[   14878214]  /",
+            "├── [      29700]  a",
+            "│   ├── [        584]  e",
+            "│   │   └── [        584]  i",
+            "│   └── [      29116]  f",
+            "└── [   14848514]  b.txt",
+        ]
+        .join("\n");
+        assert_eq!(expected, render_tree(&example(), RenderOptions::default()));
+    }
+
+    #[test]
+    fn render_tree_filtered() {
+        let options = RenderOptions {
+            max_depth: Some(1),
+            min_size: 30000,
+        };
+        assert_eq!(
+            "[   14878214]  /\n└── [   14848514]  b.txt",
+            render_tree(&example(), options)
+        );
+    }
+
+    #[test]
+    fn render_du_example() {
+        assert_eq!(
+            "15M\t/\n30K\t/a\n584\t/a/e",
+            render_du(&example(), RenderOptions::default())
+        );
+    }
+
+    #[test]
+    fn human_size_rounds_up() {
+        assert_eq!("1023", human_size(1023));
+        assert_eq!("1.0K", human_size(1024));
+        assert_eq!("1.1K", human_size(1025));
+        assert_eq!("10K", human_size(10 * 1024 - 1));
+        assert_eq!("47M", human_size(48381165));
+    }
+
+    #[test]
+    fn to_json_depth_limit() {
+        let options = RenderOptions {
+            max_depth: Some(0),
+            min_size: 0,
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&to_json(&example(), options).unwrap()).unwrap();
+        assert_eq!(
+            serde_json::json!({"name": "/", "kind": "dir", "size": 14878214, "children": []}),
+            json
+        );
+    }
+
+    #[test]
+    fn root_filtered_out() {
+        let options = RenderOptions {
+            max_depth: None,
+            min_size: 20000000,
+        };
+        assert_eq!("", render_tree(&example(), options));
+        assert_eq!("null", to_json(&example(), options).unwrap());
+    }
+}
@@ -0,0 +1,365 @@
+use std::fmt::Display;
+
+/// Index of a node in the [`FsTree`] arena.
+pub type NodeId = usize;
+
+/// Errors while adding nodes to a [`FsTree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeError {
+    /// Adding something to a file, or a directory where a file of the same name exists.
+    NotADirectory { path: String },
+
+    /// Adding a file where a directory of the same name exists.
+    NotAFile { path: String },
+
+    /// Adding a file which exists with a different size.
+    ConflictingSize {
+        path: String,
+        size: usize,
+        listed: usize,
+    },
+}
+
+impl Display for TreeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TreeError::NotADirectory { path } => write!(f, "{} is not a directory", path),
+            TreeError::NotAFile { path } => write!(f, "{} is not a file", path),
+            TreeError::ConflictingSize { path, size, listed } => write!(
+                f,
+                "{} is listed with size {}, but had size {} before",
+                path, listed, size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TreeError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Dir { children: Vec<NodeId> },
+    File { size: usize },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub name: String,
+
+    /// The directory containing this node, `None` only for the root.
+    pub parent: Option<NodeId>,
+
+    pub kind: NodeKind,
+}
+
+impl Node {
+    pub fn is_dir(&self) -> bool {
+        matches!(self.kind, NodeKind::Dir { .. })
+    }
+}
+
+/// Filesystem reconstructed from the terminal output.
+///
+/// All nodes live in a single vector and refer to each other by index. A node is always added
+/// after its parent, so iterating the arena backwards visits children before their parents.
+#[derive(Clone, Debug)]
+pub struct FsTree {
+    nodes: Vec<Node>,
+}
+
+impl FsTree {
+    /// Create a tree containing only the root directory.
+    pub fn new() -> Self {
+        FsTree {
+            nodes: vec![Node {
+                name: String::from("/"),
+                parent: None,
+                kind: NodeKind::Dir {
+                    children: Vec::new(),
+                },
+            }],
+        }
+    }
+
+    pub fn root(&self) -> NodeId {
+        0
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Find the child of `dir` called `name`.
+    pub fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        match &self.nodes[dir].kind {
+            NodeKind::Dir { children } => children
+                .iter()
+                .copied()
+                .find(|&id| self.nodes[id].name == name),
+            NodeKind::File { .. } => None,
+        }
+    }
+
+    /// Add a directory called `name` to `parent`, or return the existing one.
+    pub fn add_dir(&mut self, parent: NodeId, name: &str) -> Result<NodeId, TreeError> {
+        self.add_node(
+            parent,
+            name,
+            NodeKind::Dir {
+                children: Vec::new(),
+            },
+        )
+    }
+
+    /// Add a file called `name` to `parent`, or return the existing one.
+    pub fn add_file(
+        &mut self,
+        parent: NodeId,
+        name: &str,
+        size: usize,
+    ) -> Result<NodeId, TreeError> {
+        self.add_node(parent, name, NodeKind::File { size })
+    }
+
+    /// Add a node to `parent`, or return the existing node of the same name if it is of the same
+    /// kind, and for files of the same size.
+    fn add_node(
+        &mut self,
+        parent: NodeId,
+        name: &str,
+        kind: NodeKind,
+    ) -> Result<NodeId, TreeError> {
+        if !self.nodes[parent].is_dir() {
+            return Err(TreeError::NotADirectory {
+                path: self.path(parent),
+            });
+        }
+        if let Some(id) = self.child(parent, name) {
+            let path = self.path(id);
+            return match (&self.nodes[id].kind, kind) {
+                (NodeKind::Dir { .. }, NodeKind::File { .. }) => Err(TreeError::NotAFile { path }),
+                (NodeKind::File { .. }, NodeKind::Dir { .. }) => {
+                    Err(TreeError::NotADirectory { path })
+                }
+                (NodeKind::File { size }, NodeKind::File { size: listed }) if *size != listed => {
+                    Err(TreeError::ConflictingSize {
+                        path,
+                        size: *size,
+                        listed,
+                    })
+                }
+                _ => Ok(id),
+            };
+        }
+
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name: String::from(name),
+            parent: Some(parent),
+            kind,
+        });
+
+        if let NodeKind::Dir { children } = &mut self.nodes[parent].kind {
+            children.push(id);
+        }
+
+        Ok(id)
+    }
+
+    /// Look up a node by its absolute path, such as `/a/e`.
+    pub fn lookup(&self, path: &str) -> Option<NodeId> {
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(self.root(), |dir, name| self.child(dir, name))
+    }
+
+    /// Absolute path of a node.
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.nodes[current].parent {
+            names.push(self.nodes[current].name.as_str());
+            current = parent;
+        }
+        names.reverse();
+
+        format!("/{}", names.join("/"))
+    }
+
+    /// Total size of a node, including everything below it for directories.
+    pub fn size(&self, id: NodeId) -> usize {
+        match &self.nodes[id].kind {
+            NodeKind::Dir { children } => children.iter().map(|&child| self.size(child)).sum(),
+            NodeKind::File { size } => *size,
+        }
+    }
+
+    /// Total size of the node at `path`.
+    pub fn size_of(&self, path: &str) -> Option<usize> {
+        self.lookup(path).map(|id| self.size(id))
+    }
+
+    /// Children of the directory at `path`, or `None` if there is no such directory.
+    pub fn children(&self, path: &str) -> Option<&[NodeId]> {
+        match &self.nodes[self.lookup(path)?].kind {
+            NodeKind::Dir { children } => Some(children),
+            NodeKind::File { .. } => None,
+        }
+    }
+
+    /// Total size of every node, indexed by [`NodeId`], computed in a single pass.
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.nodes.len()];
+        for (id, node) in self.nodes.iter().enumerate().rev() {
+            if let NodeKind::File { size } = node.kind {
+                sizes[id] = size;
+            }
+            if let Some(parent) = node.parent {
+                sizes[parent] += sizes[id];
+            }
+        }
+
+        sizes
+    }
+
+    /// Depth-first walk over all nodes, starting at the root, with children in the order they were
+    /// first listed.
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            tree: self,
+            stack: vec![(self.root(), 0)],
+        }
+    }
+}
+
+impl Default for FsTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct WalkEntry<'a> {
+    pub id: NodeId,
+    pub node: &'a Node,
+
+    /// Number of directories between the root and this node, 0 for the root itself.
+    pub depth: usize,
+}
+
+/// Iterator returned by [`FsTree::walk`].
+pub struct Walk<'a> {
+    tree: &'a FsTree,
+    stack: Vec<(NodeId, usize)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = WalkEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, depth) = self.stack.pop()?;
+        let node = self.tree.node(id);
+        if let NodeKind::Dir { children } = &node.kind {
+            self.stack
+                .extend(children.iter().rev().map(|&child| (child, depth + 1)));
+        }
+
+        Some(WalkEntry { id, node, depth })
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::log::example;
+
+    #[test]
+    fn size_of_example() {
+        let tree = example();
+        assert_eq!(Some(584), tree.size_of("/a/e"));
+        assert_eq!(Some(94853), tree.size_of("/a"));
+        assert_eq!(Some(24933642), tree.size_of("/d"));
+        assert_eq!(Some(48381165), tree.size_of("/"));
+        assert_eq!(None, tree.size_of("/x"));
+
+        let sizes = tree.sizes();
+        assert_eq!(tree.len(), sizes.len());
+        for (id, size) in sizes.into_iter().enumerate() {
+            assert_eq!(tree.size(id), size);
+        }
+    }
+
+    #[test]
+    fn children_example() {
+        let tree = example();
+        let names: Vec<_> = tree
+            .children("/a")
+            .unwrap()
+            .iter()
+            .map(|&id| tree.node(id).name.as_str())
+            .collect();
+        assert_eq!(vec!["e", "f", "g", "h.lst"], names);
+        assert_eq!(None, tree.children("/b.txt"));
+    }
+
+    #[test]
+    fn walk_example() {
+        let tree = example();
+        let paths: Vec<_> = tree
+            .walk()
+            .map(|entry| (tree.path(entry.id), entry.depth))
+            .take(4)
+            .collect();
+        assert_eq!(
+            vec![
+                (String::from("/"), 0),
+                (String::from("/a"), 1),
+                (String::from("/a/e"), 2),
+                (String::from("/a/e/i"), 3),
+            ],
+            paths
+        );
+        assert_eq!(tree.len(), tree.walk().count());
+    }
+
+    #[test]
+    fn conflicting_kinds() {
+        let mut tree = example();
+        let a = tree.lookup("/a").unwrap();
+        let f = tree.lookup("/a/f").unwrap();
+        assert_eq!(Ok(a), tree.add_dir(tree.root(), "a"));
+        assert_eq!(Ok(f), tree.add_file(a, "f", 29116));
+        assert_eq!(
+            Err(TreeError::NotAFile {
+                path: String::from("/a")
+            }),
+            tree.add_file(tree.root(), "a", 1)
+        );
+        assert_eq!(
+            Err(TreeError::NotADirectory {
+                path: String::from("/a/f")
+            }),
+            tree.add_dir(a, "f")
+        );
+        assert_eq!(
+            Err(TreeError::NotADirectory {
+                path: String::from("/a/f")
+            }),
+            tree.add_file(f, "x", 1)
+        );
+        assert_eq!(
+            Err(TreeError::ConflictingSize {
+                path: String::from("/a/f"),
+                size: 29116,
+                listed: 29117
+            }),
+            tree.add_file(a, "f", 29117)
+        );
+        assert_eq!(14, tree.len());
+    }
+}
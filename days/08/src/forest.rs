@@ -0,0 +1,193 @@
+/// Direction in which a tree house looks out, in the order the puzzle lists them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Towards row 0.
+    Up,
+
+    /// Towards column 0.
+    Left,
+
+    /// Towards the last row.
+    Down,
+
+    /// Towards the last column.
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Left,
+        Direction::Down,
+        Direction::Right,
+    ];
+}
+
+/// Dense row-major grid with one value per tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    pub rows: usize,
+    pub cols: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(rows: usize, cols: usize, value: T) -> Self {
+        Grid {
+            rows,
+            cols,
+            cells: vec![value; rows * cols],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn get(&self, row: usize, col: usize) -> &T {
+        &self.cells[row * self.cols + col]
+    }
+
+    pub fn get_mut(&mut self, row: usize, col: usize) -> &mut T {
+        &mut self.cells[row * self.cols + col]
+    }
+}
+
+/// Visibility and viewing distances of every tree in the forest.
+pub struct Analysis {
+    /// Whether the tree can be seen from outside the forest, in each direction.
+    pub visible: Grid<[bool; 4]>,
+
+    /// Number of trees that can be seen from the tree in each direction, indexed by [`Direction`].
+    pub distances: Grid<[usize; 4]>,
+}
+
+impl Analysis {
+    /// Analyse every tree of the forest in O(rows × cols).
+    ///
+    /// Each row and column is swept once per direction with a monotonic stack holding the trees
+    /// which are not yet blocked by a taller or equally tall tree. When a tree arrives, every
+    /// shorter tree on the stack is popped, since it can't block the view of anything after it.
+    /// Whatever is left on top is the first tree blocking the view back towards the edge.
+    pub fn new(forest: &[Vec<i8>]) -> Self {
+        let rows = forest.len();
+        let cols = forest.first().map_or(0, Vec::len);
+
+        let mut analysis = Analysis {
+            visible: Grid::new(rows, cols, [false; 4]),
+            distances: Grid::new(rows, cols, [0; 4]),
+        };
+
+        // Looking in a direction is the same as sweeping from that edge of the forest.
+        for direction in Direction::ALL {
+            let lines: Vec<Vec<(usize, usize)>> = match direction {
+                Direction::Up => (0..cols)
+                    .map(|col| (0..rows).map(|row| (row, col)).collect())
+                    .collect(),
+                Direction::Down => (0..cols)
+                    .map(|col| (0..rows).rev().map(|row| (row, col)).collect())
+                    .collect(),
+                Direction::Left => (0..rows)
+                    .map(|row| (0..cols).map(|col| (row, col)).collect())
+                    .collect(),
+                Direction::Right => (0..rows)
+                    .map(|row| (0..cols).rev().map(|col| (row, col)).collect())
+                    .collect(),
+            };
+
+            let mut stack: Vec<usize> = Vec::with_capacity(rows.max(cols));
+            for line in lines {
+                stack.clear();
+                for (idx, &(row, col)) in line.iter().enumerate() {
+                    let height = forest[row][col];
+                    while let Some(&top) = stack.last() {
+                        let (r, c) = line[top];
+                        if forest[r][c] >= height {
+                            break;
+                        }
+                        stack.pop();
+                    }
+
+                    let distance = match stack.last() {
+                        Some(&blocker) => idx - blocker,
+                        None => idx,
+                    };
+                    analysis.distances.get_mut(row, col)[direction as usize] = distance;
+                    analysis.visible.get_mut(row, col)[direction as usize] = stack.is_empty();
+                    stack.push(idx);
+                }
+            }
+        }
+
+        analysis
+    }
+
+    pub fn is_visible(&self, row: usize, col: usize) -> bool {
+        self.visible.get(row, col).iter().any(|v| *v)
+    }
+
+    /// Number of trees that can be seen from the tree, in the order of [`Direction::ALL`].
+    pub fn viewing_distances(&self, row: usize, col: usize) -> [usize; 4] {
+        *self.distances.get(row, col)
+    }
+
+    pub fn scenic_score(&self, row: usize, col: usize) -> usize {
+        self.viewing_distances(row, col).iter().product()
+    }
+}
+
+/// The heights from the puzzle description.
+#[cfg(test)]
+pub fn example() -> Vec<Vec<i8>> {
+    ["30373", "25512", "65332", "33549", "35390"]
+        .iter()
+        .map(|line| line.bytes().map(|b| (b - b'0') as i8).collect())
+        .collect()
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn visible_example() {
+        let forest = example();
+        let analysis = Analysis::new(&forest);
+        let visible = (0..5)
+            .flat_map(|row| (0..5).map(move |col| (row, col)))
+            .filter(|&(row, col)| analysis.is_visible(row, col))
+            .count();
+        assert_eq!(21, visible);
+        assert!(!analysis.is_visible(1, 3));
+        assert!(analysis.is_visible(1, 2));
+    }
+
+    #[test]
+    fn scenic_score_example() {
+        let analysis = Analysis::new(&example());
+        assert_eq!(4, analysis.scenic_score(1, 2));
+        assert_eq!(8, analysis.scenic_score(3, 2));
+        assert_eq!(0, analysis.scenic_score(0, 0));
+    }
+
+    #[test]
+    fn viewing_distances_example() {
+        let analysis = Analysis::new(&example());
+
+        // Up, left, down, right, as described in the puzzle.
+        assert_eq!([1, 1, 2, 2], analysis.viewing_distances(1, 2));
+        assert_eq!([2, 2, 1, 2], analysis.viewing_distances(3, 2));
+        assert_eq!(
+            1,
+            analysis.viewing_distances(3, 2)[Direction::Down as usize]
+        );
+        assert_eq!([0, 0, 2, 2], analysis.viewing_distances(0, 0));
+        assert_eq!([1, 1, 0, 0], analysis.viewing_distances(4, 4));
+    }
+
+    #[test]
+    fn visible_from_direction() {
+        let analysis = Analysis::new(&example());
+
+        // The 5 at (1, 1) is visible from the top and from the left, but not from the other sides.
+        assert_eq!(&[true, true, false, false], analysis.visible.get(1, 1));
+    }
+}
@@ -1,31 +1,66 @@
 use std::{
-    collections::{HashMap, HashSet},
     fs::File,
     io::{BufRead, BufReader},
-    iter::repeat,
+    path::PathBuf,
 };
 
 use anyhow::Result;
 use itertools::Itertools;
 
-#[derive(Debug)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
+use forest::Analysis;
+use render::Heatmap;
+
+mod forest;
+mod render;
 
 fn main() -> Result<()> {
     let forest = parse_input("input.txt")?;
-    println!("{}", forest.iter().map(|r| r.iter().join("")).join("\n"));
+    let analysis = Analysis::new(&forest);
+
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().is_none() {
+        println!("{}", forest.iter().map(|r| r.iter().join("")).join("\n"));
+        problem_1(&forest, &analysis)?;
+        problem_2(&analysis)?;
+        return Ok(());
+    }
 
-    problem_1(&forest)?;
-    problem_2(&forest)?;
+    // Options are applied in order, so `--heatmap` and `--scale` affect the images after them.
+    let mut heatmap = Heatmap::Scenic;
+    let mut scale = 4;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--heatmap" => heatmap = parse_heatmap(&value()?)?,
+            "--scale" => scale = value()?.parse()?,
+            "--ansi" => println!(
+                "{}",
+                render::render_ansi(&forest, &analysis, parse_heatmap(&value()?)?)
+            ),
+            "--ppm" => render::write_ppm(&PathBuf::from(value()?), &analysis, heatmap, scale)?,
+            "--png" => render::write_png(&PathBuf::from(value()?), &analysis, heatmap, scale)?,
+            "--top" => {
+                let trees = render::top_trees(&analysis, value()?.parse()?);
+                println!("{}", render::render_top_trees(&trees));
+            }
+            x => anyhow::bail!("Unknown option {}", x),
+        }
+    }
 
     Ok(())
 }
 
+fn parse_heatmap(name: &str) -> Result<Heatmap> {
+    match name {
+        "scenic" => Ok(Heatmap::Scenic),
+        "visible" => Ok(Heatmap::Visibility),
+        x => anyhow::bail!("Unknown heatmap \"{}\", expected scenic or visible", x),
+    }
+}
+
 fn parse_input(path: &str) -> Result<Vec<Vec<i8>>> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
@@ -37,60 +72,7 @@ fn parse_input(path: &str) -> Result<Vec<Vec<i8>>> {
     Ok(forest)
 }
 
-fn add_visible(
-    forest: &[Vec<i8>],
-    visible: &mut HashSet<(usize, usize)>,
-    mut row: isize,
-    mut col: isize,
-    direction: Direction,
-) {
-    let mut prev = -1;
-    let rows = forest.len() as isize;
-    let cols = forest[0].len() as isize;
-    while row >= 0 && row < rows && col >= 0 && col < cols {
-        let tree = forest[row as usize][col as usize];
-        if prev < tree {
-            visible.insert((row as usize, col as usize));
-            prev = tree;
-        }
-
-        match direction {
-            Direction::Up => row -= 1,
-            Direction::Down => row += 1,
-            Direction::Left => col -= 1,
-            Direction::Right => col += 1,
-        }
-    }
-}
-
-fn problem_1(forest: &[Vec<i8>]) -> Result<()> {
-    let mut visible = HashSet::new();
-
-    let rows = forest.len();
-    let cols = forest[0].len();
-
-    for row in 0..rows {
-        add_visible(forest, &mut visible, row as isize, 0, Direction::Right);
-        add_visible(
-            forest,
-            &mut visible,
-            row as isize,
-            (cols as isize) - 1,
-            Direction::Left,
-        );
-    }
-
-    for col in 0..cols {
-        add_visible(forest, &mut visible, 0, col as isize, Direction::Down);
-        add_visible(
-            forest,
-            &mut visible,
-            (rows as isize) - 1,
-            col as isize,
-            Direction::Up,
-        );
-    }
-
+fn problem_1(forest: &[Vec<i8>], analysis: &Analysis) -> Result<()> {
     println!();
     println!(
         "{}",
@@ -100,7 +82,7 @@ fn problem_1(forest: &[Vec<i8>]) -> Result<()> {
             .map(|(r, row)| row
                 .iter()
                 .enumerate()
-                .map(|(c, t)| if visible.contains(&(r, c)) {
+                .map(|(c, t)| if analysis.is_visible(r, c) {
                     format!("{}", t)
                 } else {
                     String::from(" ")
@@ -108,58 +90,23 @@ fn problem_1(forest: &[Vec<i8>]) -> Result<()> {
                 .join(""))
             .join("\n")
     );
-    println!("Number of trees visible from edge: {}", visible.len());
-
-    Ok(())
-}
-
-fn add_visible_trees(forest: &[Vec<i8>], row: usize, col: usize, direction: Direction) -> i32 {
-    let rows = forest.len();
-    let cols = forest[0].len();
 
-    let range: Box<dyn Iterator<Item = (usize, usize)>> = match direction {
-        Direction::Up => Box::new((row+1..rows).zip(repeat(col))),
-        Direction::Down => Box::new((0..row).rev().zip(repeat(col))),
-        Direction::Left => Box::new(repeat(row).zip(col+1..cols)),
-        Direction::Right => Box::new(repeat(row).zip((0..col).rev())),
-    };
+    let visible = (0..analysis.visible.rows)
+        .cartesian_product(0..analysis.visible.cols)
+        .filter(|&(row, col)| analysis.is_visible(row, col))
+        .count();
+    println!("Number of trees visible from edge: {}", visible);
 
-    let orig = forest[row][col];
-    let mut count = 0;
-    for (r, c) in range {
-        // println!("looping ({}, {})", r, c);
-        count += 1;
-        if forest[r][c] >= orig {
-            break;
-        }
-    }
-    // println!(
-    //     "({}, {}) has {} visible tree's to the {:?}",
-    //     row, col, count, direction
-    // );
-    count
+    Ok(())
 }
 
-fn problem_2(forest: &[Vec<i8>]) -> Result<()> {
-    let mut scores: HashMap<(usize, usize), i32> = HashMap::new();
-
-    let rows = forest.len();
-    let cols = forest[0].len();
-
-    for row in 0..rows {
-        for col in 0..cols {
-            let mut count = 1;
-            count *= add_visible_trees(forest, row, col, Direction::Up);
-            count *= add_visible_trees(forest, row, col, Direction::Down);
-            count *= add_visible_trees(forest, row, col, Direction::Left);
-            count *= add_visible_trees(forest, row, col, Direction::Right);
-            scores.insert((row, col), count);
-        }
-    }
-
-    let mut sorted_scores: Vec<_> = scores.into_iter().collect();
-    sorted_scores.sort_by_key(|(_, v)| *v);
-    println!("Problem 2: {:?}", sorted_scores.last().unwrap().1);
+fn problem_2(analysis: &Analysis) -> Result<()> {
+    let best = (0..analysis.distances.rows)
+        .cartesian_product(0..analysis.distances.cols)
+        .map(|(row, col)| analysis.scenic_score(row, col))
+        .max()
+        .unwrap_or(0);
+    println!("Problem 2: {:?}", best);
 
     Ok(())
 }
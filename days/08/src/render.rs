@@ -0,0 +1,219 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Result;
+use itertools::Itertools;
+
+use crate::forest::{Analysis, Direction};
+
+/// Which property of the trees is shown by the colours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Heatmap {
+    /// Scenic score, relative to the best tree in the forest.
+    Scenic,
+
+    /// Whether the tree can be seen from outside the forest.
+    Visibility,
+}
+
+type Rgb = [u8; 3];
+
+/// Colour of every tree, row by row.
+fn colours(analysis: &Analysis, heatmap: Heatmap) -> Vec<Vec<Rgb>> {
+    let (rows, cols) = (analysis.distances.rows, analysis.distances.cols);
+    let max_score = (0..rows)
+        .cartesian_product(0..cols)
+        .map(|(row, col)| analysis.scenic_score(row, col))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    (0..rows)
+        .map(|row| {
+            (0..cols)
+                .map(|col| match heatmap {
+                    Heatmap::Scenic => {
+                        ramp(analysis.scenic_score(row, col) as f64 / max_score as f64)
+                    }
+                    Heatmap::Visibility if analysis.is_visible(row, col) => [60, 200, 60],
+                    Heatmap::Visibility => [20, 40, 20],
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Map a value between 0 and 1 onto a dark blue, green, yellow colour ramp.
+fn ramp(value: f64) -> Rgb {
+    const STOPS: [Rgb; 4] = [[10, 10, 60], [20, 120, 80], [120, 200, 40], [250, 230, 50]];
+
+    let scaled = value.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let idx = (scaled as usize).min(STOPS.len() - 2);
+    let t = scaled - idx as f64;
+    let (from, to) = (STOPS[idx], STOPS[idx + 1]);
+
+    [0, 1, 2].map(|i| (from[i] as f64 + (to[i] as f64 - from[i] as f64) * t).round() as u8)
+}
+
+/// Render the forest for a terminal, with the height of each tree on a 24-bit colour background.
+pub fn render_ansi(forest: &[Vec<i8>], analysis: &Analysis, heatmap: Heatmap) -> String {
+    colours(analysis, heatmap)
+        .iter()
+        .zip(forest)
+        .map(|(colours, trees)| {
+            let line: String = colours
+                .iter()
+                .zip(trees)
+                .map(|([r, g, b], tree)| {
+                    // Pick a text colour which is readable on the background.
+                    let fg = if (*r as u32 + *g as u32 + *b as u32) > 384 {
+                        30
+                    } else {
+                        97
+                    };
+                    format!("\x1b[{};48;2;{};{};{}m{}", fg, r, g, b, tree)
+                })
+                .collect();
+            format!("{}\x1b[0m", line)
+        })
+        .join("\n")
+}
+
+/// Colours of the image pixels, row by row, with every tree drawn as a `scale` × `scale` square.
+fn pixels(analysis: &Analysis, heatmap: Heatmap, scale: usize) -> (usize, usize, Vec<u8>) {
+    let colours = colours(analysis, heatmap);
+    let width = analysis.distances.cols * scale;
+    let height = analysis.distances.rows * scale;
+
+    let mut data = Vec::with_capacity(width * height * 3);
+    for row in &colours {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|rgb| std::iter::repeat_n(rgb, scale).flatten().copied())
+            .collect();
+        for _ in 0..scale {
+            data.extend_from_slice(&line);
+        }
+    }
+
+    (width, height, data)
+}
+
+/// Write the heatmap as a binary PPM image.
+pub fn write_ppm(path: &Path, analysis: &Analysis, heatmap: Heatmap, scale: usize) -> Result<()> {
+    let (width, height, data) = pixels(analysis, heatmap, scale);
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(writer, "P6\n{} {}\n255\n", width, height)?;
+    writer.write_all(&data)?;
+
+    Ok(())
+}
+
+/// Write the heatmap as a PNG image.
+pub fn write_png(path: &Path, analysis: &Analysis, heatmap: Heatmap, scale: usize) -> Result<()> {
+    let (width, height, data) = pixels(analysis, heatmap, scale);
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        width as u32,
+        height as u32,
+    );
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&data)?;
+
+    Ok(())
+}
+
+/// A tree with one of the best scenic scores in the forest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopTree {
+    pub row: usize,
+    pub col: usize,
+    pub score: usize,
+
+    /// Viewing distance in each direction, in the order of [`Direction::ALL`].
+    pub distances: [usize; 4],
+}
+
+/// The `k` trees with the highest scenic score, best first. Ties are broken by position.
+pub fn top_trees(analysis: &Analysis, k: usize) -> Vec<TopTree> {
+    (0..analysis.distances.rows)
+        .cartesian_product(0..analysis.distances.cols)
+        .map(|(row, col)| TopTree {
+            row,
+            col,
+            score: analysis.scenic_score(row, col),
+            distances: analysis.viewing_distances(row, col),
+        })
+        .sorted_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| (a.row, a.col).cmp(&(b.row, b.col)))
+        })
+        .take(k)
+        .collect()
+}
+
+/// Render the top trees as a table, with one column per direction.
+pub fn render_top_trees(trees: &[TopTree]) -> String {
+    let header = format!(
+        "{:>4} {:>4} {:>8} {}",
+        "row",
+        "col",
+        "score",
+        Direction::ALL
+            .iter()
+            .map(|d| format!("{:>6}", format!("{:?}", d).to_lowercase()))
+            .join(" ")
+    );
+
+    std::iter::once(header)
+        .chain(trees.iter().map(|tree| {
+            format!(
+                "{:>4} {:>4} {:>8} {}",
+                tree.row,
+                tree.col,
+                tree.score,
+                tree.distances.iter().map(|d| format!("{:>6}", d)).join(" ")
+            )
+        }))
+        .join("\n")
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::forest::example;
+
+    #[test]
+    fn top_trees_example() {
+        let analysis = Analysis::new(&example());
+        let top = top_trees(&analysis, 2);
+        assert_eq!(2, top.len());
+        assert_eq!((3, 2, 8), (top[0].row, top[0].col, top[0].score));
+        assert_eq!((2, 1, 6), (top[1].row, top[1].col, top[1].score));
+    }
+
+    #[test]
+    fn pixels_are_scaled() {
+        let analysis = Analysis::new(&example());
+        let (width, height, data) = pixels(&analysis, Heatmap::Visibility, 3);
+        assert_eq!((15, 15), (width, height));
+        assert_eq!(15 * 15 * 3, data.len());
+
+        // The hidden tree at (1, 3) is dark, its visible neighbour at (1, 2) is bright.
+        let pixel = |x: usize, y: usize| &data[(y * width + x) * 3..(y * width + x) * 3 + 3];
+        assert_eq!(&[20, 40, 20], pixel(3 * 3 + 1, 3 + 1));
+        assert_eq!(&[60, 200, 60], pixel(2 * 3 + 2, 3 + 2));
+    }
+
+    #[test]
+    fn ramp_ends() {
+        assert_eq!([10, 10, 60], ramp(0.0));
+        assert_eq!([250, 230, 50], ramp(1.0));
+    }
+}
@@ -1,19 +1,32 @@
 use std::{
-    collections::HashSet,
     fmt::Display,
     fs::File,
     io::{BufRead, BufReader},
-    iter::repeat,
+    path::PathBuf,
     str::FromStr,
+    time::Duration,
 };
 
 use anyhow::Result;
 
-enum Dir {
+use visualise::{Mode, Viewport};
+
+mod rope;
+mod visualise;
+
+pub enum Dir {
     Up,
     Down,
     Left,
     Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+
+    /// Out of the plane, towards positive `z`.
+    Forward,
+    Backward,
 }
 
 impl FromStr for Dir {
@@ -25,6 +38,12 @@ impl FromStr for Dir {
             "D" => Ok(Dir::Down),
             "L" => Ok(Dir::Left),
             "R" => Ok(Dir::Right),
+            "UL" => Ok(Dir::UpLeft),
+            "UR" => Ok(Dir::UpRight),
+            "DL" => Ok(Dir::DownLeft),
+            "DR" => Ok(Dir::DownRight),
+            "F" => Ok(Dir::Forward),
+            "B" => Ok(Dir::Backward),
             _ => Err(format!("Could not create direction from '{}'", s)),
         }
     }
@@ -36,10 +55,16 @@ impl Display for Dir {
             f,
             "{}",
             match self {
-                Dir::Up => "Up",
-                Dir::Down => "Down",
-                Dir::Left => "Left",
-                Dir::Right => "Right",
+                Dir::Up => "U",
+                Dir::Down => "D",
+                Dir::Left => "L",
+                Dir::Right => "R",
+                Dir::UpLeft => "UL",
+                Dir::UpRight => "UR",
+                Dir::DownLeft => "DL",
+                Dir::DownRight => "DR",
+                Dir::Forward => "F",
+                Dir::Backward => "B",
             }
         )?;
 
@@ -47,11 +72,26 @@ impl Display for Dir {
     }
 }
 
-struct Inst {
+pub struct Inst {
     dir: Dir,
     count: i32,
 }
 
+impl FromStr for Inst {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let dir = Dir::from_str(parts.next().unwrap_or_default())?;
+        let count = match parts.next().map(str::parse) {
+            Some(Ok(count)) => count,
+            _ => return Err(format!("Could not parse step count from '{}'", s)),
+        };
+
+        Ok(Inst { dir, count })
+    }
+}
+
 impl Display for Inst {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} {}", self.dir, self.count)?;
@@ -60,22 +100,69 @@ impl Display for Inst {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Loc {
-    x: isize,
-    y: isize,
-}
-
 fn main() -> Result<()> {
     // let instructions = parse_input("example.txt")?;
     // let instructions = parse_input("example2.txt")?;
     let instructions = parse_input("input.txt")?;
 
-    // for inst in &instructions {
-    //     println!("{}", inst);
-    // }
-    problem_1(&instructions);
-    problem_2(&instructions);
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().is_none() {
+        problem_1(&instructions);
+        problem_2(&instructions);
+        return Ok(());
+    }
+
+    // Options are applied in order, so they only affect the outputs after them.
+    let mut knots = 10;
+    let mut mode = Mode::Instruction;
+    let mut cell_size = 4;
+    let mut trail = true;
+    let mut delay = Duration::from_millis(100);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--knots" => {
+                knots = value()?.parse()?;
+                if knots == 0 {
+                    anyhow::bail!("A rope needs at least one knot");
+                }
+            }
+            "--mode" => {
+                mode = match value()?.as_str() {
+                    "step" => Mode::Step,
+                    "instruction" => Mode::Instruction,
+                    "final" => Mode::Final,
+                    x => anyhow::bail!("Unknown mode {}, expected step, instruction or final", x),
+                }
+            }
+            "--no-trail" => trail = false,
+            "--cell-size" => cell_size = value()?.parse()?,
+            "--delay" => delay = Duration::from_millis(value()?.parse()?),
+            "--show" => {
+                for frame in visualise::frames(knots, &instructions, mode).with_trail(trail) {
+                    println!("{}", frame.to_text());
+                }
+            }
+            "--animate" => visualise::animate(
+                visualise::frames(knots, &instructions, mode).with_trail(trail),
+                delay,
+            )?,
+            "--text" => visualise::write_text(
+                &PathBuf::from(value()?),
+                visualise::frames(knots, &instructions, mode).with_trail(trail),
+            )?,
+            "--gif" => {
+                let frames = visualise::frames(knots, &instructions, mode)
+                    .with_trail(trail)
+                    .with_viewport(Viewport::bounds(knots, &instructions));
+                visualise::write_gif(&PathBuf::from(value()?), frames, cell_size, delay)?;
+            }
+            x => anyhow::bail!("Unknown option {}", x),
+        }
+    }
 
     Ok(())
 }
@@ -85,140 +172,18 @@ fn parse_input(path: &str) -> Result<Vec<Inst>> {
     let reader = BufReader::new(file);
     let instructions: Vec<_> = reader
         .lines()
-        .map(|s| s.unwrap())
-        .map(|line| {
-            let mut parts = line.split_whitespace();
-            let dir = Dir::from_str(parts.next().unwrap()).unwrap();
-            let count = parts.next().unwrap().parse().unwrap();
-            Inst { dir, count }
-        })
+        .map(|s| Inst::from_str(&s.unwrap()).unwrap())
         .collect();
 
     Ok(instructions)
 }
 
-fn too_far(a: &Loc, b: &Loc) -> bool {
-    (a.x - b.x).abs() > 1 || (a.y - b.y).abs() > 1
-}
-
 fn problem_1(instructions: &[Inst]) {
-    let mut head = Loc { x: 0, y: 0 };
-    let mut tail = Loc { x: 0, y: 0 };
-
-    let mut visited: HashSet<Loc> = HashSet::new();
-
-    // Add current location;
-    visited.insert(tail);
-
-    for Inst { dir, count } in instructions {
-        let dhead = match dir {
-            Dir::Up => (1, 0),
-            Dir::Down => (-1, 0),
-            Dir::Left => (0, -1),
-            Dir::Right => (0, 1),
-        };
-
-        // Iterate over the number of steps in that direction.
-        for _ in 0..*count {
-            // println!("Before moving: Head: ({}, {})", head.x, head.y);
-            // Move the head.
-            head.x += dhead.0;
-            head.y += dhead.1;
-
-            // Move the tail.
-            if too_far(&head, &tail) {
-                let normalised = (tail.x - head.x, tail.y - head.y);
-                // println!("normalised: ({}, {})", normalised.0, normalised.1);
-                let dtail = match normalised {
-                    (0, 2) => (0, -1),
-                    (0, -2) => (0, 1),
-
-                    (2, 0) => (-1, 0),
-                    (-2, 0) => (1, 0),
-
-                    (x, 2) if x.abs() == 1 => (-x, -1),
-                    (x, -2) if x.abs() == 1 => (-x, 1),
-
-                    (2, y) if y.abs() == 1 => (-1, -y),
-                    (-2, y) if y.abs() == 1 => (1, -y),
-
-                    _ => (0, 0),
-                };
-                tail.x += dtail.0;
-                tail.y += dtail.1;
-
-                visited.insert(tail);
-            }
-
-            // println!(
-            //     "After Moving: Head: ({}, {}), Tail: ({}, {})",
-            //     head.x, head.y, tail.x, tail.y
-            // );
-        }
-    }
-
-    println!("Tail was in {} locations", visited.len());
+    let visited = rope::visited(2, instructions, &[1]).unwrap();
+    println!("Tail was in {} locations", visited[0].len());
 }
 
 fn problem_2(instructions: &[Inst]) {
-    let mut rope: Vec<_> = repeat(Loc { x: 0, y: 0 }).take(10).collect();
-
-    // Track the locations the last rope segment visited.
-    let mut visited: Vec<HashSet<Loc>> = repeat(HashSet::new()).take(10).collect();
-
-    // Add current location;
-    for (idx, part) in rope.iter().enumerate() {
-        visited[idx].insert(*part);
-    }
-
-    for Inst { dir, count } in instructions {
-        let dhead = match dir {
-            Dir::Up => (1, 0),
-            Dir::Down => (-1, 0),
-            Dir::Left => (0, -1),
-            Dir::Right => (0, 1),
-        };
-
-        // Iterate over the number of steps in that direction.
-        for _ in 0..*count {
-            // println!("Before moving: Head: ({}, {})", head.x, head.y);
-            // Move the head.
-            let mut parts = rope.iter_mut().enumerate();
-            let (idx, mut head) = parts.next().unwrap();
-            visited[idx].insert(*head);
-            head.x += dhead.0;
-            head.y += dhead.1;
-
-            for (idx, tail) in parts {
-                // Move the tail.
-                if too_far(head, tail) {
-                    let nx = tail.x - head.x;
-                    let ny = tail.y - head.y;
-
-                    let (dx, dy) = match (nx, ny) {
-                        (0, 0) => (0, 0),
-                        (x, y) if x.abs() == y.abs() => {
-                            (x.signum() * (x.abs() - 1), y.signum() * (y.abs() - 1))
-                        }
-                        (x, y) if x.abs() > y.abs() => (x.signum() * (x.abs() - 1), y),
-                        (x, y) if x.abs() < y.abs() => (x, y.signum() * (y.abs() - 1)),
-                        (x, y) => unreachable!("Unexpected motion: ({}, {})", x, y),
-                    };
-                    println!("(nx: {}, ny: {}) => (dx: {}, dy: {})", nx, ny, -dx, -dy);
-
-                    tail.x -= dx;
-                    tail.y -= dy;
-                }
-
-                visited[idx].insert(*tail);
-                head = tail;
-            }
-            // println!(
-            //     "After Moving: Head: ({}, {}), Tail: ({}, {})",
-            //     head.x, head.y, tail.x, tail.y
-            // );
-        }
-    }
-
-    println!("Tail was in {} locations", visited[9].len());
+    let visited = rope::visited(10, instructions, &[9]).unwrap();
+    println!("Tail was in {} locations", visited[0].len());
 }
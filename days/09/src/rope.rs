@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+
+use crate::{Dir, Inst};
+
+/// Number of dimensions ropes move in.
+pub const DIMS: usize = 3;
+
+/// Location of a knot, with one coordinate per axis: `x`, `y` and `z`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Loc(pub [isize; DIMS]);
+
+impl Loc {
+    /// Location in the `x`-`y` plane, where the puzzle's ropes move.
+    pub fn plane(x: isize, y: isize) -> Self {
+        let mut coords = [0; DIMS];
+        coords[0] = x;
+        coords[1] = y;
+        Loc(coords)
+    }
+
+    pub fn x(&self) -> isize {
+        self.0[0]
+    }
+
+    pub fn y(&self) -> isize {
+        self.0[1]
+    }
+
+    /// The location seen from above, with every other axis dropped.
+    pub fn project(&self) -> Self {
+        Loc::plane(self.x(), self.y())
+    }
+}
+
+impl Dir {
+    /// Offset of a single step in this direction. Up is towards positive `y`.
+    pub fn delta(&self) -> Loc {
+        match self {
+            Dir::Up => Loc::plane(0, 1),
+            Dir::Down => Loc::plane(0, -1),
+            Dir::Left => Loc::plane(-1, 0),
+            Dir::Right => Loc::plane(1, 0),
+            Dir::UpLeft => Loc::plane(-1, 1),
+            Dir::UpRight => Loc::plane(1, 1),
+            Dir::DownLeft => Loc::plane(-1, -1),
+            Dir::DownRight => Loc::plane(1, -1),
+            Dir::Forward => Loc([0, 0, 1]),
+            Dir::Backward => Loc([0, 0, -1]),
+        }
+    }
+}
+
+/// Rope made of a number of knots, the first of which is the head.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rope {
+    knots: Vec<Loc>,
+}
+
+impl Rope {
+    /// Create a rope of `n` knots, all starting at the origin.
+    pub fn new(n: usize) -> Self {
+        assert!(n > 0, "a rope needs at least one knot");
+
+        Rope {
+            knots: vec![Loc::default(); n],
+        }
+    }
+
+    pub fn knots(&self) -> &[Loc] {
+        &self.knots
+    }
+
+    /// Move the head by one step and let every other knot follow the one in front of it.
+    ///
+    /// A knot only moves once it is no longer touching the knot in front, i.e. once it is more
+    /// than one step away along any axis. It then moves one step closer along every axis, which
+    /// covers both straight and diagonal catch-up moves in any number of dimensions.
+    pub fn step(&mut self, dir: &Dir) {
+        let delta = dir.delta();
+        for (coord, d) in self.knots[0].0.iter_mut().zip(delta.0) {
+            *coord += d;
+        }
+
+        for idx in 1..self.knots.len() {
+            let head = self.knots[idx - 1];
+            let tail = &mut self.knots[idx];
+            let diff: [isize; DIMS] = std::array::from_fn(|axis| head.0[axis] - tail.0[axis]);
+            if diff.iter().all(|d| d.abs() <= 1) {
+                // Once a knot stays put, so does everything behind it.
+                break;
+            }
+            for (coord, d) in tail.0.iter_mut().zip(diff) {
+                *coord += d.signum();
+            }
+        }
+    }
+
+    /// Iterate over the state of the rope after every single step of the instructions.
+    pub fn simulate(self, instructions: &[Inst]) -> Steps<'_> {
+        Steps {
+            rope: self,
+            instructions,
+            inst: 0,
+            done: 0,
+        }
+    }
+}
+
+/// State of the rope after a single step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    /// Index of the instruction this step belongs to.
+    pub instruction: usize,
+
+    /// Whether this is the last step of the instruction.
+    pub last: bool,
+
+    pub knots: Vec<Loc>,
+}
+
+/// Iterator returned by [`Rope::simulate`].
+pub struct Steps<'a> {
+    rope: Rope,
+    instructions: &'a [Inst],
+
+    /// Current instruction and how many of its steps have been done.
+    inst: usize,
+    done: i32,
+}
+
+impl Iterator for Steps<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Skip instructions which don't move at all.
+        while self.done >= self.instructions.get(self.inst)?.count {
+            self.inst += 1;
+            self.done = 0;
+        }
+
+        let Inst { dir, count } = &self.instructions[self.inst];
+        self.rope.step(dir);
+        self.done += 1;
+
+        Some(Step {
+            instruction: self.inst,
+            last: self.done == *count,
+            knots: self.rope.knots().to_vec(),
+        })
+    }
+}
+
+/// Simulate a rope of `n` knots and return the locations visited by each of the `tracked` knots,
+/// including the starting point, or `None` if any of them is not a knot of the rope.
+pub fn visited(n: usize, instructions: &[Inst], tracked: &[usize]) -> Option<Vec<HashSet<Loc>>> {
+    if n == 0 || tracked.iter().any(|&knot| knot >= n) {
+        return None;
+    }
+
+    let mut visited = vec![HashSet::from([Loc::default()]); tracked.len()];
+    for step in Rope::new(n).simulate(instructions) {
+        for (set, &knot) in visited.iter_mut().zip(tracked) {
+            set.insert(step.knots[knot]);
+        }
+    }
+
+    Some(visited)
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[allow(dead_code)]
+    fn parse(input: &str) -> Vec<Inst> {
+        input.lines().map(|line| line.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn visited_example() {
+        let instructions = parse("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2");
+        let visited = visited(10, &instructions, &[1, 9]).unwrap();
+        assert_eq!(13, visited[0].len());
+        assert_eq!(1, visited[1].len());
+    }
+
+    #[test]
+    fn visited_larger_example() {
+        let instructions = parse("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20");
+        assert_eq!(36, visited(10, &instructions, &[9]).unwrap()[0].len());
+    }
+
+    #[test]
+    fn visited_unknown_knot() {
+        let instructions = parse("R 4\nU 4");
+        assert_eq!(None, visited(2, &instructions, &[0, 2]));
+        assert_eq!(None, visited(0, &instructions, &[]));
+        assert_eq!(Some(vec![]), visited(2, &instructions, &[]));
+    }
+
+    #[test]
+    fn simulate_yields_every_step() {
+        let instructions = parse("R 4\nU 0\nU 2");
+        let steps: Vec<_> = Rope::new(2).simulate(&instructions).collect();
+        assert_eq!(6, steps.len());
+        assert_eq!(2, steps[4].instruction);
+        assert!(steps[3].last && steps[5].last && !steps[4].last);
+        assert_eq!(vec![Loc::plane(4, 2), Loc::plane(4, 1)], steps[5].knots);
+    }
+
+    #[test]
+    fn diagonal_moves() {
+        let instructions = parse("UR 3\nDL 1\nDR 2");
+        let steps: Vec<_> = Rope::new(2).simulate(&instructions).collect();
+        assert_eq!(vec![Loc::plane(3, 3), Loc::plane(2, 2)], steps[2].knots);
+        assert_eq!(vec![Loc::plane(2, 2), Loc::plane(2, 2)], steps[3].knots);
+        assert_eq!(vec![Loc::plane(4, 0), Loc::plane(3, 1)], steps[5].knots);
+        assert_eq!("UR 3", instructions[0].to_string());
+    }
+
+    #[test]
+    fn three_dimensional_moves() {
+        let instructions = parse("R 1\nU 1\nF 2\nB 4");
+        let steps: Vec<_> = Rope::new(3).simulate(&instructions).collect();
+
+        // The first knot catches up along all three axes at once.
+        assert_eq!(
+            vec![Loc([1, 1, 2]), Loc([1, 1, 1]), Loc([0, 0, 0])],
+            steps[3].knots
+        );
+        assert_eq!(
+            vec![Loc([1, 1, -2]), Loc([1, 1, -1]), Loc([0, 0, 0])],
+            steps[7].knots
+        );
+        let visited = visited(3, &instructions, &[1, 2]).unwrap();
+        assert_eq!((4, 1), (visited[0].len(), visited[1].len()));
+    }
+}
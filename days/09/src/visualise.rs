@@ -0,0 +1,391 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    thread::sleep,
+    time::Duration,
+};
+
+use anyhow::Result;
+
+use crate::{
+    rope::{Loc, Rope, Steps},
+    Inst,
+};
+
+/// Which states of the rope become frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// The initial state and every single step.
+    Step,
+
+    /// The initial state and the state after every instruction, like the puzzle's examples.
+    Instruction,
+
+    /// Only the locations visited by the tail at the very end.
+    Final,
+}
+
+/// Part of the `x`-`y` plane shown in a frame, inclusive on both ends. Any other axes are ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub min: Loc,
+    pub max: Loc,
+}
+
+impl Viewport {
+    /// Viewport containing only the given location.
+    pub fn around(loc: Loc) -> Self {
+        let loc = loc.project();
+        Viewport { min: loc, max: loc }
+    }
+
+    /// Grow the viewport until it contains `loc`.
+    pub fn include(&mut self, loc: Loc) {
+        self.min = Loc::plane(self.min.x().min(loc.x()), self.min.y().min(loc.y()));
+        self.max = Loc::plane(self.max.x().max(loc.x()), self.max.y().max(loc.y()));
+    }
+
+    pub fn contains(&self, loc: Loc) -> bool {
+        (self.min.x()..=self.max.x()).contains(&loc.x())
+            && (self.min.y()..=self.max.y()).contains(&loc.y())
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.x() - self.min.x() + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.y() - self.min.y() + 1) as usize
+    }
+
+    /// Viewport containing every location any knot reaches during the simulation.
+    pub fn bounds(knots: usize, instructions: &[Inst]) -> Self {
+        let mut viewport = Viewport::around(Loc::default());
+        for step in Rope::new(knots).simulate(instructions) {
+            step.knots.iter().for_each(|loc| viewport.include(*loc));
+        }
+
+        viewport
+    }
+}
+
+/// A single picture of the rope seen from above, using the notation of the puzzle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub title: String,
+    pub viewport: Viewport,
+
+    /// Cells from the top row to the bottom one. Up is towards positive `y`.
+    pub cells: Vec<Vec<char>>,
+}
+
+impl Frame {
+    /// The character at `loc`, or `.` if it lies outside of the frame.
+    pub fn at(&self, loc: Loc) -> char {
+        let vp = &self.viewport;
+        if !vp.contains(loc) {
+            return '.';
+        }
+        self.cells[(vp.max.y() - loc.y()) as usize][(loc.x() - vp.min.x()) as usize]
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("== {} ==\n", self.title);
+        for row in &self.cells {
+            text.extend(row);
+            text.push('\n');
+        }
+
+        text
+    }
+}
+
+/// Label of the knot at `idx` in a rope of `len` knots.
+fn label(idx: usize, len: usize) -> char {
+    match idx {
+        0 => 'H',
+        _ if len == 2 => 'T',
+        // Knots after the 9th continue with letters.
+        _ => char::from_digit(idx as u32, 36).unwrap_or('*'),
+    }
+}
+
+/// Draw the knots and the visited locations, which are already projected onto the plane.
+fn draw(title: String, viewport: Viewport, knots: &[Loc], visited: &HashSet<Loc>) -> Frame {
+    let start = Loc::default();
+    let cells = (viewport.min.y()..=viewport.max.y())
+        .rev()
+        .map(|y| {
+            (viewport.min.x()..=viewport.max.x())
+                .map(|x| {
+                    let loc = Loc::plane(x, y);
+                    // Knots closer to the head cover the ones behind them.
+                    match knots.iter().position(|knot| knot.project() == loc) {
+                        Some(idx) => label(idx, knots.len()),
+                        None if loc == start => 's',
+                        None if visited.contains(&loc) => '#',
+                        None => '.',
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    Frame {
+        title,
+        viewport,
+        cells,
+    }
+}
+
+/// Iterator over the frames of a simulation, see [`frames`].
+pub struct Frames<'a> {
+    steps: Steps<'a>,
+    instructions: &'a [Inst],
+    mode: Mode,
+    viewport: Viewport,
+
+    /// Locations visited by the tail, projected onto the plane, which are marked with `#`.
+    visited: HashSet<Loc>,
+
+    /// Whether to mark the visited locations before the final frame.
+    trail: bool,
+
+    /// Steps done in the current instruction.
+    done: usize,
+
+    /// Whether the first frame, and for the final mode the last frame, have been produced.
+    started: bool,
+    finished: bool,
+    last: Vec<Loc>,
+}
+
+impl Frames<'_> {
+    /// Start with a larger viewport, e.g. to give every frame the same size.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport.include(viewport.min);
+        self.viewport.include(viewport.max);
+        self
+    }
+
+    /// Whether to mark the locations visited so far in every frame. The puzzle's examples only
+    /// show them at the very end.
+    pub fn with_trail(mut self, trail: bool) -> Self {
+        self.trail = trail;
+        self
+    }
+
+    fn draw(&self, title: String, knots: &[Loc]) -> Frame {
+        let no_trail = HashSet::new();
+        let visited = if self.trail || knots.is_empty() {
+            &self.visited
+        } else {
+            &no_trail
+        };
+
+        draw(title, self.viewport, knots, visited)
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if self.mode != Mode::Final {
+                let title = String::from("Initial State");
+                return Some(self.draw(title, &self.last));
+            }
+        }
+
+        for step in self.steps.by_ref() {
+            step.knots
+                .iter()
+                .for_each(|loc| self.viewport.include(*loc));
+            self.visited.insert(step.knots.last().unwrap().project());
+            self.last = step.knots;
+
+            self.done += 1;
+            let done = self.done;
+            if step.last {
+                self.done = 0;
+            }
+
+            let inst = &self.instructions[step.instruction];
+            let title = match self.mode {
+                Mode::Step => format!("{} ({}/{})", inst, done, inst.count),
+                Mode::Instruction if step.last => format!("{}", inst),
+                _ => continue,
+            };
+
+            return Some(self.draw(title, &self.last));
+        }
+
+        if self.mode == Mode::Final && !self.finished {
+            self.finished = true;
+            let title = String::from("Final State");
+            return Some(self.draw(title, &[]));
+        }
+
+        None
+    }
+}
+
+/// Simulate a rope of `knots` knots and draw it, growing the viewport whenever a knot leaves it.
+pub fn frames(knots: usize, instructions: &[Inst], mode: Mode) -> Frames<'_> {
+    Frames {
+        steps: Rope::new(knots).simulate(instructions),
+        instructions,
+        mode,
+        viewport: Viewport::around(Loc::default()),
+        visited: HashSet::from([Loc::default()]),
+        trail: true,
+        done: 0,
+        started: false,
+        finished: false,
+        last: vec![Loc::default(); knots],
+    }
+}
+
+/// Show the frames one after another in the terminal.
+pub fn animate(frames: impl Iterator<Item = Frame>, delay: Duration) -> Result<()> {
+    let mut stdout = io::stdout();
+    for frame in frames {
+        // Clear the screen and move the cursor to the top left corner.
+        write!(stdout, "\x1b[2J\x1b[H{}", frame.to_text())?;
+        stdout.flush()?;
+        sleep(delay);
+    }
+
+    Ok(())
+}
+
+/// Write all frames to a text file, separated by blank lines.
+pub fn write_text(path: &Path, frames: impl Iterator<Item = Frame>) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for frame in frames {
+        writeln!(writer, "{}", frame.to_text())?;
+    }
+
+    Ok(())
+}
+
+/// Write the frames as an animated GIF, drawing every cell as a `cell_size` square.
+///
+/// All frames are drawn on the viewport of the first frame, so the viewport should already be
+/// large enough from the start, see [`Viewport::bounds`] and [`Frames::with_viewport`].
+pub fn write_gif(
+    path: &Path,
+    frames: impl Iterator<Item = Frame>,
+    cell_size: usize,
+    delay: Duration,
+) -> Result<()> {
+    // Background, visited, start, head and other knots.
+    const PALETTE: [u8; 15] = [
+        30, 30, 30, 200, 160, 60, 80, 160, 230, 230, 60, 60, 240, 240, 240,
+    ];
+
+    let mut frames = frames.peekable();
+    let Some(first) = frames.peek() else {
+        return Ok(());
+    };
+    let viewport = first.viewport;
+    let width = u16::try_from(viewport.width() * cell_size)?;
+    let height = u16::try_from(viewport.height() * cell_size)?;
+
+    let mut encoder =
+        gif::Encoder::new(BufWriter::new(File::create(path)?), width, height, &PALETTE)?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    for frame in frames {
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in (viewport.min.y()..=viewport.max.y()).rev() {
+            let row: Vec<u8> = (viewport.min.x()..=viewport.max.x())
+                .flat_map(|x| {
+                    let colour = match frame.at(Loc::plane(x, y)) {
+                        '.' => 0,
+                        '#' => 1,
+                        's' => 2,
+                        'H' => 3,
+                        _ => 4,
+                    };
+                    std::iter::repeat_n(colour, cell_size)
+                })
+                .collect();
+            for _ in 0..cell_size {
+                pixels.extend_from_slice(&row);
+            }
+        }
+
+        let mut gif_frame = gif::Frame::from_indexed_pixels(width, height, pixels, None);
+        gif_frame.delay = (delay.as_millis() / 10) as u16;
+        encoder.write_frame(&gif_frame)?;
+    }
+
+    Ok(())
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[allow(dead_code)]
+    fn example() -> Vec<Inst> {
+        "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2"
+            .lines()
+            .map(|line| line.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn instruction_frames_match_puzzle() {
+        let instructions = example();
+        let frames: Vec<_> = frames(2, &instructions, Mode::Instruction)
+            .with_viewport(Viewport {
+                min: Loc::plane(0, 0),
+                max: Loc::plane(5, 4),
+            })
+            .with_trail(false)
+            .collect();
+        assert_eq!(9, frames.len());
+        assert_eq!(
+            "== R 4 ==\n......\n......\n......\n......\ns..TH.\n",
+            frames[1].to_text()
+        );
+        assert_eq!(
+            "== U 4 ==\n....H.\n....T.\n......\n......\ns.....\n",
+            frames[2].to_text()
+        );
+    }
+
+    #[test]
+    fn final_frame_shows_visited() {
+        let instructions = example();
+        let frames: Vec<_> = frames(2, &instructions, Mode::Final).collect();
+        assert_eq!(1, frames.len());
+        assert_eq!(
+            "== Final State ==\n..##..\n...##.\n.####.\n....#.\ns###..\n",
+            frames[0].to_text()
+        );
+    }
+
+    #[test]
+    fn step_frames_grow_viewport() {
+        let instructions = example();
+        let frames: Vec<_> = frames(10, &instructions, Mode::Step).collect();
+        assert_eq!(25, frames.len());
+        assert_eq!("R 4 (2/4)", frames[2].title);
+        assert_eq!("21H", frames[2].cells[0].iter().collect::<String>());
+        assert_eq!(Viewport::bounds(10, &instructions), frames[24].viewport);
+    }
+
+    #[test]
+    fn trail_marks_visited() {
+        let instructions = example();
+        let frame = frames(2, &instructions, Mode::Instruction).nth(1).unwrap();
+        assert_eq!("s##TH", frame.cells[0].iter().collect::<String>());
+    }
+}
@@ -0,0 +1,534 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+/// A register of the CPU. `X` is the one the CRT uses for the sprite position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Register {
+    X,
+    Y,
+    Z,
+}
+
+impl Register {
+    pub const ALL: [Register; 3] = [Register::X, Register::Y, Register::Z];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl FromStr for Register {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x" => Ok(Register::X),
+            "y" => Ok(Register::Y),
+            "z" => Ok(Register::Z),
+            _ => Err(format!("Could not create register from '{}'", s)),
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Register::X => "x",
+                Register::Y => "y",
+                Register::Z => "z",
+            }
+        )
+    }
+}
+
+/// A single instruction. Jump targets are indices into the program, where the index right after
+/// the last instruction halts the CPU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Add {
+        reg: Register,
+        num: i32,
+    },
+    Sub {
+        reg: Register,
+        num: i32,
+    },
+    Mul {
+        reg: Register,
+        num: i32,
+    },
+    Jmp {
+        target: usize,
+    },
+
+    /// Jump if the register is not zero.
+    Jnz {
+        reg: Register,
+        target: usize,
+    },
+}
+
+impl Instruction {
+    /// Number of cycles the instruction takes to complete.
+    pub fn cycles(&self) -> i32 {
+        match self {
+            Instruction::Noop => 1,
+            Instruction::Add { .. } | Instruction::Sub { .. } => 2,
+            Instruction::Mul { .. } => 3,
+            Instruction::Jmp { .. } => 1,
+            Instruction::Jnz { .. } => 2,
+        }
+    }
+
+    /// The instruction's jump target, if it has one.
+    pub fn target(&self) -> Option<usize> {
+        match self {
+            Instruction::Jmp { target } | Instruction::Jnz { target, .. } => Some(*target),
+            _ => None,
+        }
+    }
+
+    /// Write the instruction, naming its jump target with `target`.
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, target: &dyn Display) -> std::fmt::Result {
+        match self {
+            Instruction::Noop => write!(f, "noop"),
+            Instruction::Add { reg, num } => write!(f, "add{} {}", reg, num),
+            Instruction::Sub { reg, num } => write!(f, "sub{} {}", reg, num),
+            Instruction::Mul { reg, num } => write!(f, "mul{} {}", reg, num),
+            Instruction::Jmp { .. } => write!(f, "jmp {}", target),
+            Instruction::Jnz { reg, .. } => write!(f, "jnz {} {}", reg, target),
+        }
+    }
+}
+
+impl Display for Instruction {
+    /// Jump targets are written as instruction indices, which the assembler accepts as well.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, &self.target().unwrap_or_default())
+    }
+}
+
+/// An assembled program, which remembers the labels of the source it came from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+
+    /// Labels and the index of the instruction they point to, in the order they were defined.
+    pub labels: Vec<(String, usize)>,
+}
+
+impl Display for Program {
+    /// Disassemble the program. Jump targets without a label get one named after their index,
+    /// with a suffix if the source already uses that name.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: HashMap<usize, String> = HashMap::new();
+        for (name, idx) in &self.labels {
+            names.entry(*idx).or_insert_with(|| name.clone());
+        }
+        for target in self.instructions.iter().filter_map(Instruction::target) {
+            if names.contains_key(&target) {
+                continue;
+            }
+            let taken = |name: &str| self.labels.iter().any(|(label, _)| label == name);
+            let mut name = format!("L{}", target);
+            while taken(&name) {
+                name.push('_');
+            }
+            names.insert(target, name);
+        }
+
+        for idx in 0..=self.instructions.len() {
+            // Print the labels from the source, or a generated one for unlabelled jump targets.
+            for (name, _) in self.labels.iter().filter(|(_, i)| *i == idx) {
+                writeln!(f, "{}:", name)?;
+            }
+            if !self.labels.iter().any(|(_, i)| *i == idx) {
+                if let Some(name) = names.get(&idx) {
+                    writeln!(f, "{}:", name)?;
+                }
+            }
+
+            let Some(inst) = self.instructions.get(idx) else {
+                break;
+            };
+            write!(f, "    ")?;
+            match inst.target() {
+                Some(target) => inst.write(f, &names[&target])?,
+                None => write!(f, "{}", inst)?,
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AsmError {
+    /// A mnemonic which is not part of the instruction set.
+    UnknownOpcode {
+        line: usize,
+        opcode: String,
+    },
+
+    /// An instruction without all of the operands it needs.
+    MissingOperand {
+        line: usize,
+    },
+
+    /// An operand after the ones the instruction takes.
+    UnexpectedOperand {
+        line: usize,
+        operand: String,
+    },
+
+    InvalidNumber {
+        line: usize,
+        value: String,
+    },
+    UnknownRegister {
+        line: usize,
+        name: String,
+    },
+
+    /// A label which is used but never defined.
+    UnknownLabel {
+        line: usize,
+        label: String,
+    },
+
+    /// A label which is defined twice, or whose name can't be told apart from a number.
+    InvalidLabel {
+        line: usize,
+        label: String,
+    },
+
+    /// A jump to an index after the end of the program.
+    InvalidTarget {
+        line: usize,
+        target: usize,
+    },
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsmError::UnknownOpcode { line, opcode } => {
+                write!(f, "line {}: unknown opcode \"{}\"", line, opcode)
+            }
+            AsmError::MissingOperand { line } => write!(f, "line {}: missing operand", line),
+            AsmError::UnexpectedOperand { line, operand } => {
+                write!(f, "line {}: unexpected operand \"{}\"", line, operand)
+            }
+            AsmError::InvalidNumber { line, value } => {
+                write!(f, "line {}: invalid number \"{}\"", line, value)
+            }
+            AsmError::UnknownRegister { line, name } => {
+                write!(f, "line {}: unknown register \"{}\"", line, name)
+            }
+            AsmError::UnknownLabel { line, label } => {
+                write!(f, "line {}: unknown label \"{}\"", line, label)
+            }
+            AsmError::InvalidLabel { line, label } => {
+                write!(f, "line {}: invalid or duplicate label \"{}\"", line, label)
+            }
+            AsmError::InvalidTarget { line, target } => {
+                write!(f, "line {}: jump target {} is out of range", line, target)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// A jump target before the labels have been resolved.
+enum Target<'a> {
+    Index(usize),
+    Label(&'a str),
+}
+
+impl<'a> Target<'a> {
+    fn parse(value: &'a str) -> Self {
+        match value.parse() {
+            Ok(idx) => Target::Index(idx),
+            Err(_) => Target::Label(value),
+        }
+    }
+}
+
+/// Assemble a program.
+///
+/// Every line holds at most one instruction, optionally preceded by any number of `name:`
+/// labels, with or without a space after the colon. Everything after a `;` is a comment. Jump
+/// targets are either labels or instruction indices. Arithmetic mnemonics end in the register
+/// they change, e.g. `addx 3` or `muly -2`.
+pub fn assemble(source: &str) -> Result<Program, AsmError> {
+    let mut program = Program::default();
+    // Jumps whose target has to be resolved once all labels are known.
+    let mut unresolved = Vec::new();
+
+    for (idx, text) in source.lines().enumerate() {
+        let line = idx + 1;
+        let mut code = text.split(';').next().unwrap_or_default().trim_start();
+        while let Some((label, rest)) = code
+            .split_once(':')
+            .filter(|(label, _)| !label.contains(char::is_whitespace))
+        {
+            let valid = label.chars().all(|c| c.is_alphanumeric() || c == '_')
+                && label.chars().next().is_some_and(|c| !c.is_ascii_digit());
+            if !valid || program.labels.iter().any(|(name, _)| name == label) {
+                return Err(AsmError::InvalidLabel {
+                    line,
+                    label: String::from(label),
+                });
+            }
+            program
+                .labels
+                .push((String::from(label), program.instructions.len()));
+            code = rest.trim_start();
+        }
+
+        let mut tokens = code.split_whitespace();
+        let Some(opcode) = tokens.next() else {
+            continue;
+        };
+        let mut operand = || tokens.next().ok_or(AsmError::MissingOperand { line });
+        let register = |name: &str| {
+            name.parse::<Register>()
+                .map_err(|_| AsmError::UnknownRegister {
+                    line,
+                    name: String::from(name),
+                })
+        };
+        let number = |value: &str| {
+            value.parse::<i32>().map_err(|_| AsmError::InvalidNumber {
+                line,
+                value: String::from(value),
+            })
+        };
+        let (inst, jump) = match opcode {
+            "noop" => (Instruction::Noop, None),
+            "jmp" => (
+                Instruction::Jmp { target: 0 },
+                Some(Target::parse(operand()?)),
+            ),
+            "jnz" => {
+                let reg = register(operand()?)?;
+                (
+                    Instruction::Jnz { reg, target: 0 },
+                    Some(Target::parse(operand()?)),
+                )
+            }
+            _ => {
+                let arithmetic = ["add", "sub", "mul"]
+                    .into_iter()
+                    .find_map(|op| Some((op, opcode.strip_prefix(op)?)));
+                let Some((op, reg)) = arithmetic else {
+                    return Err(AsmError::UnknownOpcode {
+                        line,
+                        opcode: String::from(opcode),
+                    });
+                };
+                let reg = register(reg)?;
+                let num = number(operand()?)?;
+                let inst = match op {
+                    "add" => Instruction::Add { reg, num },
+                    "sub" => Instruction::Sub { reg, num },
+                    _ => Instruction::Mul { reg, num },
+                };
+                (inst, None)
+            }
+        };
+
+        if let Some(extra) = tokens.next() {
+            return Err(AsmError::UnexpectedOperand {
+                line,
+                operand: String::from(extra),
+            });
+        }
+        if let Some(jump) = jump {
+            unresolved.push((program.instructions.len(), jump, line));
+        }
+        program.instructions.push(inst);
+    }
+
+    for (idx, jump, line) in unresolved {
+        let resolved = match jump {
+            Target::Index(target) if target <= program.instructions.len() => target,
+            Target::Index(target) => return Err(AsmError::InvalidTarget { line, target }),
+            Target::Label(label) => program
+                .labels
+                .iter()
+                .find(|(name, _)| name == label)
+                .map(|(_, target)| *target)
+                .ok_or_else(|| AsmError::UnknownLabel {
+                    line,
+                    label: String::from(label),
+                })?,
+        };
+        match &mut program.instructions[idx] {
+            Instruction::Jmp { target } | Instruction::Jnz { target, .. } => *target = resolved,
+            _ => unreachable!("only jumps have targets"),
+        }
+    }
+
+    Ok(program)
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn assemble_puzzle_program() {
+        let program = assemble("noop\naddx 3\naddx -5\n").unwrap();
+        assert_eq!(
+            vec![
+                Instruction::Noop,
+                Instruction::Add {
+                    reg: Register::X,
+                    num: 3
+                },
+                Instruction::Add {
+                    reg: Register::X,
+                    num: -5
+                },
+            ],
+            program.instructions
+        );
+    }
+
+    #[test]
+    fn labels_and_comments() {
+        let source = "; count down from three\n\
+                      addy 3\n\
+                      loop: suby 1 ; once per iteration\n\
+                      \n\
+                      jnz y loop\n\
+                      jmp end\n\
+                      mulx 2\n\
+                      end:\n";
+        let program = assemble(source).unwrap();
+        assert_eq!(5, program.instructions.len());
+        assert_eq!(
+            Instruction::Jnz {
+                reg: Register::Y,
+                target: 1
+            },
+            program.instructions[2]
+        );
+        assert_eq!(Instruction::Jmp { target: 5 }, program.instructions[3]);
+        assert_eq!(
+            vec![(String::from("loop"), 1), (String::from("end"), 5)],
+            program.labels
+        );
+    }
+
+    #[test]
+    fn disassemble_round_trip() {
+        let source =
+            "    addy 3\nloop:\n    suby 1\n    jnz y loop\n    jmp end\n    mulx 2\nend:\n";
+        let program = assemble(source).unwrap();
+        assert_eq!(source, program.to_string());
+        assert_eq!(program, assemble(&program.to_string()).unwrap());
+
+        // Without the labels, the jump targets are named after their index.
+        let unlabelled = Program {
+            instructions: program.instructions.clone(),
+            labels: Vec::new(),
+        };
+        assert!(unlabelled.to_string().starts_with("    addy 3\nL1:\n"));
+        assert_eq!(
+            "    jmp L1\nL1:\n    noop\n",
+            assemble("jmp 1\nnoop").unwrap().to_string()
+        );
+        assert_eq!("jnz y 1", program.instructions[2].to_string());
+    }
+
+    #[test]
+    fn generated_labels_avoid_source_labels() {
+        // `L3` and `L3_` point somewhere else than the generated label for index 3 would.
+        let source = "jmp 3\nL3: noop\nL3_: noop\nnoop\njmp L3";
+        let program = assemble(source).unwrap();
+        let disassembled = program.to_string();
+        assert!(disassembled.starts_with("    jmp L3__\n"));
+        assert_eq!(
+            program.instructions,
+            assemble(&disassembled).unwrap().instructions
+        );
+    }
+
+    #[test]
+    fn labels_without_space() {
+        let program = assemble("start:addx 1\na:b: jmp start").unwrap();
+        assert_eq!(2, program.instructions.len());
+        assert_eq!(
+            vec![
+                (String::from("start"), 0),
+                (String::from("a"), 1),
+                (String::from("b"), 1)
+            ],
+            program.labels
+        );
+        assert_eq!(Instruction::Jmp { target: 0 }, program.instructions[1]);
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let error = |source: &str| assemble(source).unwrap_err();
+        assert_eq!(
+            AsmError::UnknownOpcode {
+                line: 2,
+                opcode: String::from("divx")
+            },
+            error("noop\ndivx 2")
+        );
+        assert_eq!(AsmError::MissingOperand { line: 1 }, error("addx"));
+        assert_eq!(
+            AsmError::InvalidNumber {
+                line: 1,
+                value: String::from("three")
+            },
+            error("addx three")
+        );
+        assert_eq!(
+            AsmError::UnknownRegister {
+                line: 1,
+                name: String::from("w")
+            },
+            error("addw 1")
+        );
+        assert_eq!(
+            AsmError::UnexpectedOperand {
+                line: 1,
+                operand: String::from("4")
+            },
+            error("addx 3 4")
+        );
+        assert_eq!(
+            AsmError::UnknownLabel {
+                line: 3,
+                label: String::from("nowhere")
+            },
+            error("a:\nnoop\njmp nowhere")
+        );
+        assert_eq!(
+            AsmError::InvalidLabel {
+                line: 2,
+                label: String::from("a")
+            },
+            error("a:\na: noop")
+        );
+        assert_eq!(
+            AsmError::InvalidTarget { line: 1, target: 2 },
+            error("jmp 2")
+        );
+        assert_eq!(
+            "line 2: unknown opcode \"divx\"",
+            error("noop\ndivx 2").to_string()
+        );
+    }
+}
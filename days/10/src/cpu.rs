@@ -0,0 +1,144 @@
+use crate::asm::{Instruction, Register};
+
+/// Cycle-accurate emulator of the handheld device's CPU.
+///
+/// The CPU is an iterator over the cycles of the program, yielding the number of every cycle,
+/// starting at 1, together with the value of the `X` register *during* that cycle. An instruction
+/// only changes a register or jumps once its last cycle has completed. Programs with jumps may
+/// never finish, in which case neither does the iterator.
+#[derive(Clone, Debug)]
+pub struct Cpu<'a> {
+    program: &'a [Instruction],
+
+    /// Index of the instruction being executed.
+    pc: usize,
+
+    /// Cycles of the current instruction which have already completed.
+    elapsed: i32,
+
+    /// Number of cycles which have completed.
+    cycle: usize,
+    registers: [i32; Register::ALL.len()],
+}
+
+impl<'a> Cpu<'a> {
+    pub fn new(program: &'a [Instruction]) -> Self {
+        Cpu {
+            program,
+            pc: 0,
+            elapsed: 0,
+            cycle: 0,
+            // Only `X` starts at 1.
+            registers: [1, 0, 0],
+        }
+    }
+
+    /// Number of cycles which have completed.
+    pub fn completed_cycles(&self) -> usize {
+        self.cycle
+    }
+
+    /// Index of the instruction being executed.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The instruction being executed, or `None` if the program has finished.
+    pub fn current(&self) -> Option<&Instruction> {
+        self.program.get(self.pc)
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.current().is_none()
+    }
+
+    /// Value of a register, which is also its value during the next cycle.
+    pub fn register(&self, reg: Register) -> i32 {
+        self.registers[reg.index()]
+    }
+
+    /// Run a single cycle and return its number and the value of `X` during it, or `None` if the
+    /// program has finished.
+    pub fn tick(&mut self) -> Option<(usize, i32)> {
+        let inst = self.program.get(self.pc)?;
+        self.cycle += 1;
+        let during = (self.cycle, self.registers[Register::X.index()]);
+
+        self.elapsed += 1;
+        if self.elapsed >= inst.cycles() {
+            self.pc += 1;
+            self.elapsed = 0;
+
+            // Arithmetic wraps around instead of crashing the machine.
+            match *inst {
+                Instruction::Noop => (),
+                Instruction::Add { reg, num } => {
+                    self.registers[reg.index()] = self.registers[reg.index()].wrapping_add(num)
+                }
+                Instruction::Sub { reg, num } => {
+                    self.registers[reg.index()] = self.registers[reg.index()].wrapping_sub(num)
+                }
+                Instruction::Mul { reg, num } => {
+                    self.registers[reg.index()] = self.registers[reg.index()].wrapping_mul(num)
+                }
+                Instruction::Jmp { target } => self.pc = target,
+                Instruction::Jnz { reg, target } => {
+                    if self.registers[reg.index()] != 0 {
+                        self.pc = target;
+                    }
+                }
+            }
+        }
+
+        Some(during)
+    }
+}
+
+impl Iterator for Cpu<'_> {
+    type Item = (usize, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tick()
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn small_example() {
+        let program = crate::asm::assemble("noop\naddx 3\naddx -5").unwrap();
+        let mut cpu = Cpu::new(&program.instructions);
+        let trace: Vec<_> = cpu.by_ref().collect();
+        assert_eq!(vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)], trace);
+        assert_eq!((5, -1), (cpu.cycle, cpu.registers[0]));
+    }
+
+    #[test]
+    fn empty_program() {
+        assert_eq!(None, Cpu::new(&[]).next());
+    }
+
+    #[test]
+    fn loops_and_multiplication() {
+        // Double X three times, counting down in Y.
+        let source = "addy 3\nloop: mulx 2\nsuby 1\njnz y loop\naddz 7";
+        let program = crate::asm::assemble(source).unwrap();
+        let mut cpu = Cpu::new(&program.instructions);
+        let trace: Vec<_> = cpu.by_ref().collect();
+
+        // 2 cycles for addy, 3 × (3 + 2 + 2) for the loop and 2 for addz.
+        assert_eq!(25, trace.len());
+        assert_eq!((6, 2), trace[5]);
+        assert_eq!((25, 8), trace[24]);
+        assert_eq!([8, 0, 7], cpu.registers);
+    }
+
+    #[test]
+    fn endless_loop() {
+        let program = crate::asm::assemble("start: addx 1\njmp start").unwrap();
+        let x = Cpu::new(&program.instructions).nth(299).unwrap().1;
+        assert_eq!(101, x);
+    }
+}
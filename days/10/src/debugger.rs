@@ -0,0 +1,367 @@
+use std::{
+    fmt::Display,
+    io::{BufRead, Write},
+};
+
+use anyhow::Result;
+
+use crate::{
+    asm::{Instruction, Register},
+    cpu::Cpu,
+    display::Crt,
+};
+
+/// Cycles `continue` and `run` run at most, so that endless loops give control back.
+const MAX_CONTINUE: usize = 1_000_000;
+
+/// Condition which stops the CPU while it is running.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stop right before the cycle starts.
+    Cycle(usize),
+
+    /// Stop as soon as the register changes to the value.
+    Register(Register, i32),
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Cycle(cycle) => write!(f, "cycle {}", cycle),
+            Breakpoint::Register(reg, value) => write!(f, "{}={}", reg, value),
+        }
+    }
+}
+
+/// Debugger for a program running on the CPU.
+///
+/// The CPU always stops between two cycles. The cycle shown is the one which runs next, and as
+/// registers only change at the end of a cycle, the registers shown are also their values during
+/// that cycle.
+pub struct Debugger<'a> {
+    cpu: Cpu<'a>,
+    display: Crt,
+    breakpoints: Vec<Breakpoint>,
+
+    /// Registers whose changes are reported while running.
+    watches: Vec<Register>,
+
+    /// Pixels drawn by the CRT so far.
+    pixels: Vec<bool>,
+
+    /// Whether the CPU was already told to run, after which breakpoints are only checked once a
+    /// cycle is reached by running.
+    started: bool,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(program: &'a [Instruction], display: Crt) -> Self {
+        Debugger {
+            cpu: Cpu::new(program),
+            display,
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+            pixels: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Run a single cycle and report the changes of watched registers. Returns the breakpoint
+    /// that was hit, or `None` if there was none.
+    fn tick(&mut self, out: &mut impl Write) -> Result<Option<Breakpoint>> {
+        let before = Register::ALL.map(|reg| self.cpu.register(reg));
+        let Some((cycle, x)) = self.cpu.tick() else {
+            return Ok(None);
+        };
+        if self.pixels.len() < self.display.pixels() {
+            self.pixels.push(self.display.lit(cycle, x));
+        }
+
+        for &reg in &self.watches {
+            let value = self.cpu.register(reg);
+            if value != before[reg.index()] {
+                writeln!(
+                    out,
+                    "cycle {}: {} {} -> {}",
+                    cycle,
+                    reg,
+                    before[reg.index()],
+                    value
+                )?;
+            }
+        }
+
+        let next = self.cpu.completed_cycles() + 1;
+        let hit = self.breakpoints.iter().find(|bp| match **bp {
+            Breakpoint::Cycle(cycle) => cycle == next,
+            Breakpoint::Register(reg, value) => {
+                self.cpu.register(reg) == value && before[reg.index()] != value
+            }
+        });
+
+        Ok(hit.copied())
+    }
+
+    /// Run until `stop` returns true, a breakpoint is hit or the program finishes, running at
+    /// most `limit` cycles.
+    fn run_until(
+        &mut self,
+        out: &mut impl Write,
+        limit: usize,
+        mut stop: impl FnMut(&Cpu) -> bool,
+    ) -> Result<()> {
+        // The first cycle is reached without running, so check its breakpoints before anything
+        // runs.
+        if !std::mem::replace(&mut self.started, true) {
+            let first = Breakpoint::Cycle(self.cpu.completed_cycles() + 1);
+            if self.breakpoints.contains(&first) {
+                writeln!(out, "Breakpoint {} hit", first)?;
+                return self.print_state(out);
+            }
+        }
+
+        for _ in 0..limit {
+            if self.cpu.is_halted() || stop(&self.cpu) {
+                break;
+            }
+            if let Some(bp) = self.tick(out)? {
+                writeln!(out, "Breakpoint {} hit", bp)?;
+                break;
+            }
+        }
+
+        self.print_state(out)
+    }
+
+    fn print_state(&self, out: &mut impl Write) -> Result<()> {
+        let registers: Vec<String> = Register::ALL
+            .iter()
+            .map(|&reg| format!("{}={}", reg, self.cpu.register(reg)))
+            .collect();
+        write!(
+            out,
+            "cycle {} {} pc={}",
+            self.cpu.completed_cycles() + 1,
+            registers.join(" "),
+            self.cpu.pc()
+        )?;
+        match self.cpu.current() {
+            Some(inst) => writeln!(out, " {}", inst)?,
+            None => writeln!(out, " halted")?,
+        }
+
+        Ok(())
+    }
+
+    /// The CRT drawn so far, with the pixels which are still to be drawn left blank.
+    pub fn preview(&self) -> String {
+        let width = self.display.width;
+        (0..self.display.height)
+            .map(|row| {
+                (0..width)
+                    .map(|col| match self.pixels.get(row * width + col) {
+                        Some(true) => '#',
+                        Some(false) => '.',
+                        None => ' ',
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Execute a single command. Returns false once the session should end.
+    pub fn execute(&mut self, command: &str, out: &mut impl Write) -> Result<bool> {
+        let tokens: Vec<&str> = command.split_whitespace().collect();
+        match tokens[..] {
+            [] | ["s" | "step"] => self.run_until(out, 1, |_| false)?,
+            ["s" | "step", n] => match n.parse() {
+                Ok(n) => self.run_until(out, n, |_| false)?,
+                Err(_) => writeln!(out, "Usage: step [cycles]")?,
+            },
+            ["r" | "run", cycle] => match cycle.parse::<usize>() {
+                Ok(cycle) => {
+                    self.run_until(out, MAX_CONTINUE, |cpu| cpu.completed_cycles() + 1 >= cycle)?
+                }
+                Err(_) => writeln!(out, "Usage: run <cycle>")?,
+            },
+            ["c" | "continue"] => self.run_until(out, MAX_CONTINUE, |_| false)?,
+            ["b" | "break", condition] => match parse_breakpoint(condition) {
+                Some(bp) => {
+                    self.breakpoints.push(bp);
+                    writeln!(out, "Breakpoint {}: {}", self.breakpoints.len(), bp)?;
+                }
+                None => writeln!(out, "Usage: break <cycle> | break <register>=<value>")?,
+            },
+            ["b" | "break"] => {
+                for (idx, bp) in self.breakpoints.iter().enumerate() {
+                    writeln!(out, "Breakpoint {}: {}", idx + 1, bp)?;
+                }
+            }
+            ["d" | "delete", n] => match n.parse::<usize>() {
+                Ok(n) if (1..=self.breakpoints.len()).contains(&n) => {
+                    self.breakpoints.remove(n - 1);
+                }
+                _ => writeln!(out, "No breakpoint {}", n)?,
+            },
+            ["w" | "watch", reg] => match reg.parse::<Register>() {
+                Ok(reg) if self.watches.contains(&reg) => self.watches.retain(|r| *r != reg),
+                Ok(reg) => self.watches.push(reg),
+                Err(_) => writeln!(out, "Unknown register \"{}\"", reg)?,
+            },
+            ["p" | "print"] => self.print_state(out)?,
+            ["crt"] => writeln!(out, "{}", self.preview())?,
+            ["q" | "quit"] => return Ok(false),
+            _ => writeln!(
+                out,
+                "Unknown command \"{}\", expected one of step [n], run <cycle>, continue, \
+                 break [condition], delete <n>, watch <register>, print, crt, quit",
+                command.trim()
+            )?,
+        }
+
+        Ok(true)
+    }
+
+    /// Read commands line by line until the input ends or the session is quit.
+    ///
+    /// Lines starting with `#` are comments, which makes it easy to keep debugging sessions in
+    /// files and replay them. With `echo`, every command is written to the output before it is
+    /// executed, so that the output of a replayed session reads like an interactive one.
+    pub fn run(&mut self, input: impl BufRead, out: &mut impl Write, echo: bool) -> Result<()> {
+        self.print_state(out)?;
+        for line in input.lines() {
+            let line = line?;
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+            if echo {
+                writeln!(out, "> {}", line)?;
+            }
+            if !self.execute(&line, out)? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a breakpoint, which is either a cycle number or a `register=value` condition.
+fn parse_breakpoint(condition: &str) -> Option<Breakpoint> {
+    match condition.split_once('=') {
+        Some((reg, value)) => Some(Breakpoint::Register(reg.parse().ok()?, value.parse().ok()?)),
+        None => Some(Breakpoint::Cycle(condition.parse().ok()?)),
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    /// Replay a debugging session and return everything it printed.
+    #[allow(dead_code)]
+    fn replay(source: &str, script: &str) -> String {
+        let program = crate::asm::assemble(source).unwrap();
+        let mut debugger = Debugger::new(&program.instructions, Crt::default());
+        let mut out = Vec::new();
+        debugger.run(script.as_bytes(), &mut out, true).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn step_and_run() {
+        let out = replay(
+            "noop\naddx 3\naddx -5",
+            "# comments are skipped\nstep\nstep 2\nrun 5\nrun 1\nstep 10\nq\nstep",
+        );
+        assert_eq!(
+            "cycle 1 x=1 y=0 z=0 pc=0 noop\n\
+             > step\n\
+             cycle 2 x=1 y=0 z=0 pc=1 addx 3\n\
+             > step 2\n\
+             cycle 4 x=4 y=0 z=0 pc=2 addx -5\n\
+             > run 5\n\
+             cycle 5 x=4 y=0 z=0 pc=2 addx -5\n\
+             > run 1\n\
+             cycle 5 x=4 y=0 z=0 pc=2 addx -5\n\
+             > step 10\n\
+             cycle 6 x=-1 y=0 z=0 pc=3 halted\n\
+             > q\n",
+            out
+        );
+    }
+
+    #[test]
+    fn breakpoints_and_watches() {
+        let source = "addy 3\nloop: addx 5\nsuby 1\njnz y loop";
+        let out = replay(source, "b 4\nb y=1\nwatch y\ncontinue\ncontinue\nd 1\nc\nb");
+        assert_eq!(
+            "cycle 1 x=1 y=0 z=0 pc=0 addy 3\n\
+             > b 4\n\
+             Breakpoint 1: cycle 4\n\
+             > b y=1\n\
+             Breakpoint 2: y=1\n\
+             > watch y\n\
+             > continue\n\
+             cycle 2: y 0 -> 3\n\
+             Breakpoint cycle 4 hit\n\
+             cycle 4 x=1 y=3 z=0 pc=1 addx 5\n\
+             > continue\n\
+             cycle 6: y 3 -> 2\n\
+             cycle 12: y 2 -> 1\n\
+             Breakpoint y=1 hit\n\
+             cycle 13 x=11 y=1 z=0 pc=3 jnz y 1\n\
+             > d 1\n\
+             > c\n\
+             cycle 18: y 1 -> 0\n\
+             cycle 21 x=16 y=0 z=0 pc=4 halted\n\
+             > b\n\
+             Breakpoint 1: y=1\n",
+            out
+        );
+    }
+
+    #[test]
+    fn crt_preview() {
+        let out = replay("noop\naddx 3\naddx -5", "step 4\ncrt\nx");
+        assert!(out.contains("> crt\n####\n\n\n\n\n\n"));
+        assert!(out.ends_with("Unknown command \"x\", expected one of step [n], run <cycle>, continue, break [condition], delete <n>, watch <register>, print, crt, quit\n"));
+    }
+
+    #[test]
+    fn endless_loop_gives_control_back() {
+        let out = replay("start: jmp start", "c");
+        assert!(out.ends_with(&format!(
+            "cycle {} x=1 y=0 z=0 pc=0 jmp 0\n",
+            MAX_CONTINUE + 1
+        )));
+    }
+
+    #[test]
+    fn breakpoint_on_first_cycle() {
+        let out = replay("noop\naddx 3", "b 1\nc\nc");
+        assert_eq!(
+            "cycle 1 x=1 y=0 z=0 pc=0 noop\n\
+             > b 1\n\
+             Breakpoint 1: cycle 1\n\
+             > c\n\
+             Breakpoint cycle 1 hit\n\
+             cycle 1 x=1 y=0 z=0 pc=0 noop\n\
+             > c\n\
+             cycle 4 x=4 y=0 z=0 pc=2 halted\n",
+            out
+        );
+    }
+
+    #[test]
+    fn run_gives_control_back() {
+        let out = replay("start: jmp start", "run 2000000");
+        assert!(out.ends_with(&format!(
+            "cycle {} x=1 y=0 z=0 pc=0 jmp 0\n",
+            MAX_CONTINUE + 1
+        )));
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Result;
+
+use crate::{asm::Instruction, cpu::Cpu};
+
+/// Geometry of the CRT and the cycles the signal strength is sampled at.
+///
+/// The beam draws one pixel per cycle, row by row from the top left corner, and the sprite is
+/// centred on the value of `X`. With an even width it extends one pixel further to the right.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Crt {
+    /// Pixels per row. A CRT 0 pixels wide has no pixels at all, so nothing is ever lit.
+    pub width: usize,
+    pub height: usize,
+    pub sprite_width: usize,
+
+    /// Cycles whose signal strength adds up to the answer of the first problem.
+    pub sample_cycles: Vec<usize>,
+}
+
+impl Default for Crt {
+    /// The display of the handheld device.
+    fn default() -> Self {
+        Crt {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+            sample_cycles: vec![20, 60, 100, 140, 180, 220],
+        }
+    }
+}
+
+impl Crt {
+    /// Number of pixels on the screen.
+    pub fn pixels(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Whether the pixel drawn during `cycle` is lit, which is the case if the sprite covers it.
+    /// Cycles start at 1, so nothing is drawn during cycle 0.
+    pub fn lit(&self, cycle: usize, x: i32) -> bool {
+        let Some(column) = cycle
+            .checked_sub(1)
+            .and_then(|cycle| cycle.checked_rem(self.width))
+        else {
+            return false;
+        };
+        let column = column as i64;
+        let left = x as i64 - (self.sprite_width as i64 - 1) / 2;
+        (left..left + self.sprite_width as i64).contains(&column)
+    }
+
+    /// Sum of the signal strengths during the sampled cycles.
+    pub fn signal_strength(&self, instructions: &[Instruction]) -> i32 {
+        let last = self.sample_cycles.iter().max().copied().unwrap_or(0);
+
+        Cpu::new(instructions)
+            .take(last)
+            .filter(|(cycle, _)| self.sample_cycles.contains(cycle))
+            .map(|(cycle, x)| cycle as i32 * x)
+            .sum()
+    }
+
+    /// Pixels drawn by the CRT, row by row. The screen is only drawn once, and if the program
+    /// finishes before that, the remaining pixels are missing.
+    pub fn draw(&self, instructions: &[Instruction]) -> Vec<bool> {
+        Cpu::new(instructions)
+            .take(self.pixels())
+            .map(|(cycle, x)| self.lit(cycle, x))
+            .collect()
+    }
+
+    /// Render the pixels as text, with `#` for lit pixels and `off` for dark ones.
+    pub fn render_text(&self, pixels: &[bool], off: char) -> String {
+        if self.width == 0 {
+            return String::new();
+        }
+
+        pixels
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(|&lit| if lit { '#' } else { off })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Write the pixels as a binary PPM image, drawing every pixel as a `scale` × `scale` square.
+    /// Missing pixels are dark.
+    pub fn write_ppm(&self, path: &Path, pixels: &[bool], scale: usize) -> Result<()> {
+        const LIT: [u8; 3] = [255, 190, 60];
+        const DARK: [u8; 3] = [20, 20, 30];
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(
+            writer,
+            "P6\n{} {}\n255\n",
+            self.width * scale,
+            self.height * scale
+        )?;
+        for row in 0..self.height {
+            let line: Vec<u8> = (0..self.width)
+                .flat_map(|col| {
+                    let colour = match pixels.get(row * self.width + col) {
+                        Some(true) => LIT,
+                        _ => DARK,
+                    };
+                    std::iter::repeat_n(colour, scale).flatten()
+                })
+                .collect();
+            for _ in 0..scale {
+                writer.write_all(&line)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn sprite_covers_columns() {
+        let display = Crt::default();
+        assert!(!display.lit(1, 2));
+        assert!(display.lit(2, 2));
+        assert!(display.lit(4, 2));
+        assert!(!display.lit(5, 2));
+        // The beam starts over in the first column of the next row.
+        assert!(display.lit(41, 0));
+        assert!(!display.lit(0, 0));
+
+        let wide = Crt {
+            sprite_width: 4,
+            ..Crt::default()
+        };
+        assert!(!wide.lit(1, 2) && wide.lit(2, 2) && wide.lit(5, 2) && !wide.lit(6, 2));
+
+        let thin = Crt {
+            sprite_width: 1,
+            ..Crt::default()
+        };
+        assert!(!thin.lit(2, 2) && thin.lit(3, 2) && !thin.lit(4, 2));
+    }
+
+    #[test]
+    fn small_screen() {
+        let program = crate::asm::assemble("noop\naddx 3\naddx -5\nnoop\nnoop\naddx 4").unwrap();
+        let display = Crt {
+            width: 4,
+            height: 2,
+            sprite_width: 3,
+            sample_cycles: vec![4, 6],
+        };
+        let pixels = display.draw(&program.instructions);
+        assert_eq!("####\n....", display.render_text(&pixels, '.'));
+        assert_eq!(16 - 6, display.signal_strength(&program.instructions));
+    }
+
+    #[test]
+    fn no_columns() {
+        let program = crate::asm::assemble("noop\naddx 3").unwrap();
+        let display = Crt {
+            width: 0,
+            ..Crt::default()
+        };
+        assert!(!display.lit(1, 0));
+        assert_eq!(Vec::<bool>::new(), display.draw(&program.instructions));
+        assert_eq!("", display.render_text(&[true], '.'));
+    }
+}
@@ -1,163 +1,108 @@
-use std::{
-    fs::File,
-    io::{BufRead, BufReader},
-    str::FromStr,
-};
+use std::{fs::File, io::BufReader, path::PathBuf};
 
 use anyhow::Result;
-use itertools::Itertools;
+use serde::Serialize;
 
-#[derive(Debug, PartialEq, Eq)]
-enum Instruction {
-    Noop,
-    Addx { num: i32 },
-}
-
-impl Instruction {
-    fn cycles(&self) -> i32 {
-        match self {
-            Instruction::Noop => 1,
-            Instruction::Addx { num: _ } => 2,
-        }
-    }
-}
+use asm::{Instruction, Program};
+use debugger::Debugger;
+use display::Crt;
 
-impl FromStr for Instruction {
-    type Err = String;
+mod asm;
+mod cpu;
+mod debugger;
+mod display;
+mod ocr;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split_whitespace();
-        let cmd = parts.next().unwrap();
-
-        match cmd {
-            "noop" => Ok(Instruction::Noop),
-            "addx" => Ok(Instruction::Addx {
-                num: parts.next().unwrap().parse().unwrap(),
-            }),
-            x => Err(format!("Failed to parse {} as a command", x)),
-        }
-    }
+fn parse_input(path: &str) -> Result<Program> {
+    Ok(asm::assemble(&std::fs::read_to_string(path)?)?)
 }
 
-fn parse_input(path: &str) -> Result<Vec<Instruction>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    Ok(reader
-        .lines()
-        .map(|s| Instruction::from_str(s.unwrap().as_str()).unwrap())
-        .collect())
+fn problem_1(instructions: &[Instruction], display: &Crt) {
+    println!("Problem 1: {}", display.signal_strength(instructions));
 }
 
-fn problem_1(instructions: &[Instruction]) {
-    const COUNTER_SCORES: [i32; 6] = [20, 60, 100, 140, 180, 220];
-    let mut score = 0;
-
-    let mut program_counter = 0;
-    let mut insts = instructions.iter();
-    let mut inst = insts.next().unwrap();
-    let mut inst_cycles = inst.cycles();
-
-    // Registers
-    let mut register_x = 1;
+fn problem_2(instructions: &[Instruction], display: &Crt) {
+    let crt = display.draw(instructions);
 
-    loop {
-        // Increment program counter at the end.
-        program_counter += 1;
-
-        // println!(
-        //     "LOOP - PC: {} INST: {:?} CYC: {} SCORE: {}",
-        //     program_counter, inst, inst_cycles, score
-        // );
-
-        // check whether we care about the current counter.
-        if COUNTER_SCORES.contains(&program_counter) {
-            score += program_counter * register_x;
-        }
-
-        // Check whether the current command still has cycles left.
-        if inst_cycles > 1 {
-            inst_cycles -= 1;
-            continue;
-        }
-
-        // If command is finished, process it.
-        match inst {
-            Instruction::Noop => (),
-            Instruction::Addx { num } => register_x += num,
-        }
+    println!("Problem 2");
+    println!();
+    println!("{}", display.render_text(&crt, ' '));
 
-        // Get next instruction
-        let Some(i) = insts.next() else {
-            break;
-        };
-        inst = i;
-        inst_cycles = i.cycles();
+    match ocr::decode(&crt, display.width) {
+        Ok(letters) => println!("Letters: {}", letters),
+        Err(err) => println!("Could not read the letters: {}", err),
     }
-
-    println!("Problem 1: {}", score);
 }
 
-fn problem_2(instructions: &[Instruction]) {
-    const COUNTER_SCORES: [i32; 6] = [20, 60, 100, 140, 180, 220];
-    let mut score = 0;
-
-    let mut program_counter = 0;
-    let mut insts = instructions.iter();
-    let mut inst = insts.next().unwrap();
-    let mut inst_cycles = inst.cycles();
-
-    let mut crt: Vec<bool> = Vec::with_capacity(240);
-
-    // Registers
-    let mut register_x: i32 = 1;
-
-    loop {
-        // Increment program counter at the end.
-        program_counter += 1;
-
-
-        // println!(
-        //     "LOOP - PC: {} INST: {:?} CYC: {} SCORE: {}",
-        //     program_counter, inst, inst_cycles, score
-        // );
-
-        // Check whether sprite is visible.
-        let x_pos = (program_counter - 1) % 40;
-        println!("{} - {}", x_pos, register_x);
-        crt.push(register_x.abs_diff(x_pos) <= 1);
-
-        // Check whether the current command still has cycles left.
-        if inst_cycles > 1 {
-            inst_cycles -= 1;
-            continue;
-        }
-
-        // If command is finished, process it.
-        match inst {
-            Instruction::Noop => (),
-            Instruction::Addx { num } => register_x += num,
-        }
+#[derive(Serialize)]
+struct Answers {
+    problem_1: i32,
+    problem_2: String,
+}
 
-        // Get next instruction
-        let Some(i) = insts.next() else {
-            break;
-        };
-        inst = i;
-        inst_cycles = i.cycles();
+fn main() -> Result<()> {
+    let program = parse_input("input.txt")?;
+    let commands = &program.instructions;
+
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().is_none() {
+        let display = Crt::default();
+        problem_1(commands, &display);
+        problem_2(commands, &display);
+        return Ok(());
     }
 
-    println!("Problem 2");
-    for (idx, pixel) in crt.iter().enumerate() {
-        if idx % 40 == 0 {
-            println!();
+    // Options are applied in order, so the display options only affect the outputs after them.
+    let mut display = Crt::default();
+    let mut scale = 4;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--width" => {
+                display.width = value()?.parse()?;
+                if display.width == 0 {
+                    anyhow::bail!("The display needs to be at least one pixel wide");
+                }
+            }
+            "--height" => display.height = value()?.parse()?,
+            "--sprite" => display.sprite_width = value()?.parse()?,
+            "--samples" => {
+                display.sample_cycles = value()?
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?
+            }
+            "--scale" => scale = value()?.parse()?,
+            "--text" => println!("{}", display.render_text(&display.draw(commands), '.')),
+            "--ppm" => {
+                display.write_ppm(&PathBuf::from(value()?), &display.draw(commands), scale)?
+            }
+            "--json" => {
+                let answers = Answers {
+                    problem_1: display.signal_strength(commands),
+                    problem_2: ocr::decode(&display.draw(commands), display.width)?,
+                };
+                println!("{}", serde_json::to_string_pretty(&answers)?);
+            }
+            "--disassemble" => print!("{}", program),
+            "--debug" => {
+                let mut debugger = Debugger::new(commands, display.clone());
+                let mut stdout = std::io::stdout();
+                // An optional script to replay instead of reading commands from stdin.
+                match args.next_if(|arg| !arg.starts_with("--")) {
+                    Some(script) => {
+                        let file = BufReader::new(File::open(script)?);
+                        debugger.run(file, &mut stdout, true)?
+                    }
+                    None => debugger.run(std::io::stdin().lock(), &mut stdout, false)?,
+                }
+            }
+            x => anyhow::bail!("Unknown option {}", x),
         }
-        print!("{}", if *pixel { "#" } else { " " });
     }
-}
-fn main() -> Result<()> {
-    let commands = parse_input("input.txt")?;
-    problem_1(&commands);
-    problem_2(&commands);
 
     Ok(())
 }
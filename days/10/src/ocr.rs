@@ -0,0 +1,152 @@
+use std::fmt::Display;
+
+/// Height of every letter in pixels.
+pub const GLYPH_HEIGHT: usize = 6;
+
+/// Width of every letter in pixels, followed by a single empty column.
+pub const GLYPH_WIDTH: usize = 4;
+
+/// The letters the CRT is known to draw, top row first.
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OcrError {
+    /// The number of pixels doesn't match a screen of the given width and the font's height.
+    InvalidSize { pixels: usize, width: usize },
+
+    /// A letter which isn't in the font, drawn with `#` and `.`.
+    UnknownGlyph { index: usize, glyph: String },
+}
+
+impl Display for OcrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OcrError::InvalidSize { pixels, width } => write!(
+                f,
+                "{} pixels don't make up a screen {} pixels wide and {} pixels high",
+                pixels, width, GLYPH_HEIGHT
+            ),
+            OcrError::UnknownGlyph { index, glyph } => {
+                write!(f, "unknown glyph at letter {}:\n{}", index + 1, glyph)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// Read the letters drawn on a screen `width` pixels wide, whose pixels are given row by row.
+pub fn decode(pixels: &[bool], width: usize) -> Result<String, OcrError> {
+    if width == 0 || pixels.len() != width * GLYPH_HEIGHT {
+        return Err(OcrError::InvalidSize {
+            pixels: pixels.len(),
+            width,
+        });
+    }
+
+    // The empty column after the last letter may be cut off.
+    let letters = (width + 1) / (GLYPH_WIDTH + 1);
+    (0..letters)
+        .map(|index| {
+            let left = index * (GLYPH_WIDTH + 1);
+            let rows: Vec<String> = pixels
+                .chunks(width)
+                .map(|row| {
+                    row[left..left + GLYPH_WIDTH]
+                        .iter()
+                        .map(|&lit| if lit { '#' } else { '.' })
+                        .collect()
+                })
+                .collect();
+
+            FONT.iter()
+                .find(|(_, glyph)| glyph.iter().eq(rows.iter()))
+                .map(|(letter, _)| *letter)
+                .ok_or_else(|| OcrError::UnknownGlyph {
+                    index,
+                    glyph: rows.join("\n"),
+                })
+        })
+        .collect()
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    /// Draw the text with the font, leaving an empty column after every letter.
+    #[allow(dead_code)]
+    fn draw(text: &str) -> Vec<bool> {
+        (0..GLYPH_HEIGHT)
+            .flat_map(|row| {
+                text.chars().flat_map(move |c| {
+                    let (_, glyph) = FONT.iter().find(|(letter, _)| *letter == c).unwrap();
+                    glyph[row].chars().map(|p| p == '#').chain([false])
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_whole_font() {
+        let text: String = FONT.iter().map(|(letter, _)| letter).collect();
+        let width = text.len() * (GLYPH_WIDTH + 1);
+        assert_eq!(Ok(text.clone()), decode(&draw(&text), width));
+    }
+
+    #[test]
+    fn decode_without_trailing_column() {
+        let pixels: Vec<bool> = draw("RZ")
+            .chunks(10)
+            .flat_map(|row| row[..9].to_vec())
+            .collect();
+        assert_eq!(Ok(String::from("RZ")), decode(&pixels, 9));
+    }
+
+    #[test]
+    fn unknown_glyph() {
+        let mut pixels = draw("HELLO");
+        // Add a pixel to the second row of the E.
+        pixels[25 + 5 + 2] = true;
+        let err = decode(&pixels, 25).unwrap_err();
+        assert_eq!(
+            OcrError::UnknownGlyph {
+                index: 1,
+                glyph: String::from("####\n#.#.\n###.\n#...\n#...\n####"),
+            },
+            err
+        );
+        assert!(err
+            .to_string()
+            .starts_with("unknown glyph at letter 2:\n####\n"));
+    }
+
+    #[test]
+    fn invalid_size() {
+        assert_eq!(
+            Err(OcrError::InvalidSize {
+                pixels: 239,
+                width: 40
+            }),
+            decode(&[false; 239], 40)
+        );
+    }
+}
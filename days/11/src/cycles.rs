@@ -0,0 +1,256 @@
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{
+    input::Monkey,
+    worry::{Arithmetic, Relief, WorryError},
+};
+
+/// How the rounds of a simulation are run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Simulate every throw of every round.
+    BruteForce,
+
+    /// Follow every item until its state repeats, and extrapolate the remaining rounds. Worry
+    /// levels which can grow without bound never repeat, so then every throw is simulated after
+    /// all.
+    Cycles,
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "brute" => Ok(Strategy::BruteForce),
+            "cycles" => Ok(Strategy::Cycles),
+            _ => Err(format!(
+                "Unknown strategy '{}', expected brute or cycles",
+                s
+            )),
+        }
+    }
+}
+
+/// Follow a single item through one round, counting the inspections on the way. Returns the
+/// monkey holding the item at the start of the next round and its worry level.
+///
+/// Monkeys take their turns in order, so an item thrown to a later monkey is inspected again
+/// in the same round, while an item thrown to an earlier one (or back to the same one) waits
+/// for the next round.
+fn item_round<A: Arithmetic>(
+    monkeys: &[Monkey],
+    relief: Relief,
+    arithmetic: &A,
+    (mut monkey, mut value): (usize, A::Value),
+    passes: &mut [u64],
+) -> Result<(usize, A::Value), WorryError> {
+    loop {
+        let current = &monkeys[monkey];
+        passes[monkey] += 1;
+        value = arithmetic.apply(&current.operation, &value)?;
+        value = relief.apply(arithmetic, value)?;
+        let target = match arithmetic.is_divisible(&value, current.modulo_test)? {
+            true => current.if_true,
+            false => current.if_false,
+        };
+        if target <= monkey {
+            return Ok((target, value));
+        }
+        monkey = target;
+    }
+}
+
+/// Count the inspections of a single item over a number of rounds.
+///
+/// What happens to an item doesn't depend on any of the others, and its state at the start of a
+/// round is just the monkey holding it and its worry level. With a representation that keeps
+/// the worry levels bounded there are only finitely many states, so the item eventually enters
+/// a cycle. Once a state repeats, whole cycles are skipped and only the last partial one is
+/// looked up in the rounds recorded so far.
+fn item_inspections<A: Arithmetic>(
+    monkeys: &[Monkey],
+    rounds: u64,
+    relief: Relief,
+    arithmetic: &A,
+    mut state: (usize, A::Value),
+) -> Result<Vec<u64>, WorryError> {
+    let mut seen = HashMap::new();
+    // Inspections per monkey after each number of rounds.
+    let mut totals = vec![vec![0; monkeys.len()]];
+
+    for round in 0..rounds {
+        if let Some(&start) = seen.get(&state) {
+            let length = round - start;
+            let (cycles, rest) = ((rounds - round) / length, (rounds - round) % length);
+            let [start, round, rest] = [start, round, start + rest].map(|r| &totals[r as usize]);
+            return Ok((0..monkeys.len())
+                .map(|m| round[m] + cycles * (round[m] - start[m]) + (rest[m] - start[m]))
+                .collect());
+        }
+        seen.insert(state.clone(), round);
+
+        let mut passes = totals[round as usize].clone();
+        state = item_round(monkeys, relief, arithmetic, state, &mut passes)?;
+        totals.push(passes);
+    }
+
+    Ok(totals.pop().unwrap())
+}
+
+/// Count how many items each monkey inspects over a number of rounds, by detecting the cycle
+/// of every item. Gives the same result as [`crate::simulation::inspections`], but the time it takes
+/// is bounded by the cycle lengths rather than the number of rounds.
+///
+/// Items only cycle if their worry levels are bounded, either by the representation or by a
+/// relief which reduces them modulo some number. Otherwise recording every round until a state
+/// repeats would only use up memory, so this falls back to simulating every throw.
+pub fn inspections<A: Arithmetic>(
+    monkeys: &[Monkey],
+    rounds: u64,
+    relief: Relief,
+    arithmetic: &A,
+) -> Result<Vec<u64>, WorryError> {
+    if !arithmetic.is_bounded() && !matches!(relief, Relief::Modulo(_)) {
+        return crate::simulation::inspections(monkeys, rounds, relief, arithmetic);
+    }
+
+    let mut passes = vec![0; monkeys.len()];
+    for (m_idx, monkey) in monkeys.iter().enumerate() {
+        for &item in &monkey.items {
+            let state = (m_idx, arithmetic.value(item));
+            let item_passes = item_inspections(monkeys, rounds, relief, arithmetic, state)?;
+            for (total, count) in passes.iter_mut().zip(item_passes) {
+                *total += count;
+            }
+        }
+    }
+
+    Ok(passes)
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::input::example;
+    #[allow(unused_imports)]
+    use crate::worry::{self, inspections_in, Mode, Relief};
+
+    #[test]
+    fn matches_brute_force() {
+        let monkeys = example();
+        for mode in [Mode::Lcm, Mode::Residues] {
+            for rounds in [0, 1, 2, 19, 20, 500, 1000, 2345] {
+                assert_eq!(
+                    inspections_in(mode, Strategy::BruteForce, &monkeys, rounds, Relief::None),
+                    inspections_in(mode, Strategy::Cycles, &monkeys, rounds, Relief::None),
+                    "{:?} mode, {} rounds",
+                    mode,
+                    rounds
+                );
+            }
+        }
+        assert_eq!(
+            inspections_in(
+                Mode::Checked,
+                Strategy::BruteForce,
+                &monkeys,
+                20,
+                Relief::Divide(3)
+            ),
+            inspections_in(
+                Mode::Checked,
+                Strategy::Cycles,
+                &monkeys,
+                20,
+                Relief::Divide(3)
+            )
+        );
+    }
+
+    #[test]
+    fn throws_to_self_and_later_monkeys() {
+        let mut monkeys = example();
+        // Monkey 1 now keeps some items for the next round, and monkey 0 throws to monkey 2,
+        // which inspects them again in the same round.
+        monkeys[1].if_true = 1;
+        monkeys[0].if_false = 2;
+        for rounds in [1, 7, 300] {
+            assert_eq!(
+                inspections_in(
+                    Mode::Lcm,
+                    Strategy::BruteForce,
+                    &monkeys,
+                    rounds,
+                    Relief::None
+                ),
+                inspections_in(Mode::Lcm, Strategy::Cycles, &monkeys, rounds, Relief::None),
+            );
+        }
+    }
+
+    #[test]
+    fn huge_round_counts() {
+        let monkeys = example();
+        let passes =
+            inspections_in(Mode::Lcm, Strategy::Cycles, &monkeys, 10000, Relief::None).unwrap();
+        assert_eq!(2713310158, worry::monkey_business(&passes));
+
+        let passes = inspections_in(
+            Mode::Residues,
+            Strategy::Cycles,
+            &monkeys,
+            1_000_000_000_000,
+            Relief::None,
+        )
+        .unwrap();
+        // Every round every item is inspected at least once.
+        let items = monkeys.iter().map(|m| m.items.len() as u64).sum::<u64>();
+        assert!(passes.iter().sum::<u64>() >= items * 1_000_000_000_000);
+        assert_eq!(
+            passes,
+            inspections_in(
+                Mode::Lcm,
+                Strategy::Cycles,
+                &monkeys,
+                1_000_000_000_000,
+                Relief::None
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn unbounded_worry_levels() {
+        let monkeys = example();
+        // Exact values never repeat, so every throw is simulated.
+        assert_eq!(
+            inspections_in(Mode::Big, Strategy::BruteForce, &monkeys, 10, Relief::None),
+            inspections_in(Mode::Big, Strategy::Cycles, &monkeys, 10, Relief::None),
+        );
+        assert!(matches!(
+            inspections_in(
+                Mode::Checked,
+                Strategy::Cycles,
+                &monkeys,
+                1_000_000_000_000,
+                Relief::None
+            ),
+            Err(WorryError::Arithmetic { .. })
+        ));
+
+        // Reducing them modulo the LCM bounds them, so they cycle after all.
+        let modulus = worry::lcm(&monkeys).unwrap() as i64;
+        assert_eq!(
+            inspections_in(Mode::Lcm, Strategy::Cycles, &monkeys, 10000, Relief::None),
+            inspections_in(
+                Mode::Big,
+                Strategy::Cycles,
+                &monkeys,
+                10000,
+                Relief::Modulo(modulus)
+            ),
+        );
+    }
+}
@@ -1,18 +1,17 @@
-use std::{
-    fs::File,
-    io::{BufReader, Read},
-};
+use std::{fmt::Display, iter::Peekable, str::Lines};
 
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
+use crate::operation::Operation;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Monkey {
     /// List of items belonging to each monkey.
     pub items: Vec<i64>,
 
     /// How to transform the input items.
-    pub operation: fn(i64) -> i64,
+    pub operation: Operation,
 
     /// Test checks for divisibility.
     pub modulo_test: i64,
@@ -22,180 +21,334 @@ pub struct Monkey {
     pub if_false: usize,
 }
 
-pub fn get_example() -> Vec<Monkey> {
-    vec![
-        // Monkey 0:
-        //   Starting items: 79, 98
-        //   Operation: new = old * 19
-        //   Test: divisible by 23
-        //     If true: throw to monkey 2
-        //     If false: throw to monkey 3
-        Monkey {
-            items: vec![79, 98],
-            operation: |x| x * 19,
-            modulo_test: 23,
-            if_true: 2,
-            if_false: 3,
-        },
-        // Monkey 1:
-        //   Starting items: 54, 65, 75, 74
-        //   Operation: new = old + 6
-        //   Test: divisible by 19
-        //     If true: throw to monkey 2
-        //     If false: throw to monkey 0
-        Monkey {
-            items: vec![54, 65, 75, 74],
-            operation: |x| x + 6,
-            modulo_test: 19,
-            if_true: 2,
-            if_false: 0,
-        },
-        // Monkey 2:
-        //   Starting items: 79, 60, 97
-        //   Operation: new = old * old
-        //   Test: divisible by 13
-        //     If true: throw to monkey 1
-        //     If false: throw to monkey 3
-        Monkey {
-            items: vec![79, 60, 97],
-            operation: |x| x * x,
-            modulo_test: 13,
-            if_true: 1,
-            if_false: 3,
-        },
-        // Monkey 3:
-        //   Starting items: 74
-        //   Operation: new = old + 3
-        //   Test: divisible by 17
-        //     If true: throw to monkey 0
-        //     If false: throw to monkey 1
-        Monkey {
-            items: vec![74],
-            operation: |x| x + 3,
-            modulo_test: 17,
-            if_true: 0,
-            if_false: 1,
-        },
-    ]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// A line which doesn't start the way it should at this point of the notes.
+    UnexpectedLine {
+        line: usize,
+        expected: &'static str,
+        found: String,
+    },
+
+    /// The notes end in the middle of a monkey.
+    UnexpectedEnd {
+        expected: &'static str,
+    },
+
+    InvalidNumber {
+        line: usize,
+        value: String,
+    },
+    InvalidOperation {
+        line: usize,
+        operation: String,
+        reason: String,
+    },
+
+    /// A monkey which is numbered out of order, or a throw to a monkey which doesn't exist.
+    UnknownMonkey {
+        line: usize,
+        monkey: usize,
+    },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedLine {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected \"{}\", found \"{}\"",
+                line, expected, found
+            ),
+            ParseError::UnexpectedEnd { expected } => {
+                write!(f, "unexpected end of notes, expected \"{}\"", expected)
+            }
+            ParseError::InvalidNumber { line, value } => {
+                write!(f, "line {}: invalid number \"{}\"", line, value)
+            }
+            ParseError::InvalidOperation {
+                line,
+                operation,
+                reason,
+            } => write!(
+                f,
+                "line {}: invalid operation \"{}\": {}",
+                line, operation, reason
+            ),
+            ParseError::UnknownMonkey { line, monkey } => {
+                write!(f, "line {}: unexpected monkey {}", line, monkey)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The non-blank lines of the notes, with their line numbers.
+struct Notes<'a> {
+    lines: Peekable<std::iter::Enumerate<Lines<'a>>>,
+}
+
+impl<'a> Notes<'a> {
+    fn skip_blank(&mut self) {
+        while self
+            .lines
+            .next_if(|(_, line)| line.trim().is_empty())
+            .is_some()
+        {}
+    }
+
+    fn is_done(&mut self) -> bool {
+        self.skip_blank();
+        self.lines.peek().is_none()
+    }
+
+    /// The next line, which has to start with `prefix`, without the prefix.
+    fn expect(&mut self, prefix: &'static str) -> Result<(usize, &'a str), ParseError> {
+        self.skip_blank();
+        let (idx, line) = self
+            .lines
+            .next()
+            .ok_or(ParseError::UnexpectedEnd { expected: prefix })?;
+        match line.trim().strip_prefix(prefix) {
+            Some(rest) => Ok((idx + 1, rest.trim())),
+            None => Err(ParseError::UnexpectedLine {
+                line: idx + 1,
+                expected: prefix,
+                found: String::from(line.trim()),
+            }),
+        }
+    }
+}
+
+fn number<T: std::str::FromStr>(line: usize, value: &str) -> Result<T, ParseError> {
+    value.parse().map_err(|_| ParseError::InvalidNumber {
+        line,
+        value: String::from(value),
+    })
+}
+
+/// Parse the notes on the monkeys, which have to be numbered from 0 in order.
+pub fn parse_notes(notes: &str) -> Result<Vec<Monkey>, ParseError> {
+    let mut notes = Notes {
+        lines: notes.lines().enumerate().peekable(),
+    };
+    // Throws are checked once all monkeys are known.
+    let mut throws = Vec::new();
+
+    let mut monkeys = Vec::new();
+    while !notes.is_done() {
+        let (line, id) = notes.expect("Monkey ")?;
+        let id = id.strip_suffix(':').unwrap_or(id);
+        let id = number(line, id)?;
+        if id != monkeys.len() {
+            return Err(ParseError::UnknownMonkey { line, monkey: id });
+        }
+
+        let (line, items) = notes.expect("Starting items:")?;
+        let items = items
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| number(line, item))
+            .collect::<Result<_, _>>()?;
+
+        let (line, expr) = notes.expect("Operation: new =")?;
+        let operation = expr
+            .parse()
+            .map_err(|reason| ParseError::InvalidOperation {
+                line,
+                operation: String::from(expr),
+                reason,
+            })?;
+
+        let (line, divisor) = notes.expect("Test: divisible by")?;
+        let modulo_test = number(line, divisor)?;
+        if modulo_test <= 0 {
+            return Err(ParseError::InvalidNumber {
+                line,
+                value: String::from(divisor),
+            });
+        }
+
+        let mut target = |prefix| -> Result<usize, ParseError> {
+            let (line, target) = notes.expect(prefix)?;
+            let target = number(line, target)?;
+            throws.push((line, target));
+            Ok(target)
+        };
+        let if_true = target("If true: throw to monkey")?;
+        let if_false = target("If false: throw to monkey")?;
+
+        monkeys.push(Monkey {
+            items,
+            operation,
+            modulo_test,
+            if_true,
+            if_false,
+        });
+    }
+
+    if let Some(&(line, monkey)) = throws.iter().find(|(_, target)| *target >= monkeys.len()) {
+        return Err(ParseError::UnknownMonkey { line, monkey });
+    }
+
+    Ok(monkeys)
+}
+
+/// Write the monkeys in the format of the notes.
+pub fn format_notes(monkeys: &[Monkey]) -> String {
+    monkeys
+        .iter()
+        .enumerate()
+        .map(|(idx, monkey)| {
+            let items: Vec<String> = monkey.items.iter().map(i64::to_string).collect();
+            format!(
+                "Monkey {}:\n  Starting items: {}\n  Operation: new = {}\n  Test: divisible by {}\n    \
+                 If true: throw to monkey {}\n    If false: throw to monkey {}\n",
+                idx,
+                items.join(", "),
+                monkey.operation,
+                monkey.modulo_test,
+                monkey.if_true,
+                monkey.if_false
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn parse_input(path: &str) -> Result<Vec<Monkey>> {
+    Ok(parse_notes(&std::fs::read_to_string(path)?)?)
 }
 
-pub fn get_input() -> Vec<Monkey> {
-    vec![
-        // Monkey 0:
-        //   Starting items: 91, 66
-        //   Operation: new = old * 13
-        //   Test: divisible by 19
-        //     If true: throw to monkey 6
-        //     If false: throw to monkey 2
-        Monkey {
-            items: vec![91, 66],
-            operation: |x| x * 13,
-            modulo_test: 19,
-            if_true: 6,
-            if_false: 2,
-        },
-        // Monkey 1:
-        //   Starting items: 78, 97, 59
-        //   Operation: new = old + 7
-        //   Test: divisible by 5
-        //     If true: throw to monkey 0
-        //     If false: throw to monkey 3
-        Monkey {
-            items: vec![78, 97, 59],
-            operation: |x| x + 7,
-            modulo_test: 5,
-            if_true: 0,
-            if_false: 3,
-        },
-        // Monkey 2:
-        //   Starting items: 57, 59, 97, 84, 72, 83, 56, 76
-        //   Operation: new = old + 6
-        //   Test: divisible by 11
-        //     If true: throw to monkey 5
-        //     If false: throw to monkey 7
-        Monkey {
-            items: vec![57, 59, 97, 84, 72, 83, 56, 76],
-            operation: |x| x + 6,
-            modulo_test: 11,
-            if_true: 5,
-            if_false: 7,
-        },
-        //
-        // Monkey 3:
-        //   Starting items: 81, 78, 70, 58, 84
-        //   Operation: new = old + 5
-        //   Test: divisible by 17
-        //     If true: throw to monkey 6
-        //     If false: throw to monkey 0
-        Monkey {
-            items: vec![81, 78, 70, 58, 84],
-            operation: |x| x + 5,
-            modulo_test: 17,
-            if_true: 6,
-            if_false: 0,
-        },
-        // Monkey 4:
-        //   Starting items: 60
-        //   Operation: new = old + 8
-        //   Test: divisible by 7
-        //     If true: throw to monkey 1
-        //     If false: throw to monkey 3
-        Monkey {
-            items: vec![60],
-            operation: |x| x + 8,
-            modulo_test: 7,
-            if_true: 1,
-            if_false: 3,
-        },
-        // Monkey 5:
-        //   Starting items: 57, 69, 63, 75, 62, 77, 72
-        //   Operation: new = old * 5
-        //   Test: divisible by 13
-        //     If true: throw to monkey 7
-        //     If false: throw to monkey 4
-        Monkey {
-            items: vec![57, 69, 63, 75, 62, 77, 72],
-            operation: |x| x * 5,
-            modulo_test: 13,
-            if_true: 7,
-            if_false: 4,
-        },
-        // Monkey 6:
-        //   Starting items: 73, 66, 86, 79, 98, 87
-        //   Operation: new = old * old
-        //   Test: divisible by 3
-        //     If true: throw to monkey 5
-        //     If false: throw to monkey 2
-        Monkey {
-            items: vec![73, 66, 86, 79, 98, 87],
-            operation: |x| x * x,
-            modulo_test: 3,
-            if_true: 5,
-            if_false: 2,
-        },
-        // Monkey 7:
-        //   Starting items: 95, 89, 63, 67
-        //   Operation: new = old + 2
-        //   Test: divisible by 2
-        //     If true: throw to monkey 1
-        //     If false: throw to monkey 4
-        Monkey {
-            items: vec![95, 89, 63, 67],
-            operation: |x| x + 2,
-            modulo_test: 2,
-            if_true: 1,
-            if_false: 4,
-        },
-    ]
+/// The notes from the puzzle description.
+#[cfg(test)]
+pub const EXAMPLE: &str = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+
+/// The monkeys from the puzzle description.
+#[cfg(test)]
+pub fn example() -> Vec<Monkey> {
+    parse_notes(EXAMPLE).unwrap()
 }
 
-#[allow(dead_code)]
-fn parse_input(path: &str) -> Result<Vec<Monkey>> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    let mut input = String::new();
-    let bytes_read = reader.read_to_string(&mut input)?;
-    assert!(bytes_read > 0);
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn parse_example() {
+        let monkeys = parse_notes(EXAMPLE).unwrap();
+        assert_eq!(4, monkeys.len());
+        assert_eq!(
+            Monkey {
+                items: vec![79, 60, 97],
+                operation: "old * old".parse().unwrap(),
+                modulo_test: 13,
+                if_true: 1,
+                if_false: 3,
+            },
+            monkeys[2]
+        );
+        assert_eq!("old + 6", monkeys[1].operation.to_string());
+        assert_eq!(Some(19 * 2), monkeys[0].operation.eval(&2i64));
+        assert_eq!(vec![74], monkeys[3].items);
+    }
+
+    #[test]
+    fn parse_without_items() {
+        let notes = "Monkey 0:\nStarting items:\nOperation: new = old + old\n\
+                     Test: divisible by 2\nIf true: throw to monkey 0\nIf false: throw to monkey 0";
+        let monkeys = parse_notes(notes).unwrap();
+        assert!(monkeys[0].items.is_empty());
+        assert_eq!(Some(8), monkeys[0].operation.eval(&4i64));
+    }
+
+    #[test]
+    fn notes_round_trip() {
+        let monkeys = parse_notes(EXAMPLE).unwrap();
+        assert_eq!(EXAMPLE, format_notes(&monkeys));
+
+        let json = serde_json::to_string(&monkeys).unwrap();
+        assert!(json.contains(r#""operation":"old * 19""#));
+        assert_eq!(monkeys, serde_json::from_str::<Vec<Monkey>>(&json).unwrap());
+    }
 
-    Ok(Vec::new())
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            ParseError::InvalidOperation {
+                line: 17,
+                operation: String::from("old ^ 3"),
+                reason: String::from("Unexpected '^'")
+            },
+            parse_notes(&EXAMPLE.replace("old * old", "old ^ 3")).unwrap_err()
+        );
+        assert_eq!(
+            ParseError::InvalidNumber {
+                line: 2,
+                value: String::from("9x")
+            },
+            parse_notes(&EXAMPLE.replace("79, 98", "79, 9x")).unwrap_err()
+        );
+        assert_eq!(
+            ParseError::UnknownMonkey {
+                line: 27,
+                monkey: 4
+            },
+            parse_notes(
+                &EXAMPLE.replace("If false: throw to monkey 1", "If false: throw to monkey 4")
+            )
+            .unwrap_err()
+        );
+        assert_eq!(
+            ParseError::UnknownMonkey { line: 8, monkey: 2 },
+            parse_notes(&EXAMPLE.replace("Monkey 1:", "Monkey 2:")).unwrap_err()
+        );
+        assert_eq!(
+            ParseError::UnexpectedLine {
+                line: 4,
+                expected: "Test: divisible by",
+                found: String::from("Test: odd")
+            },
+            parse_notes(&EXAMPLE.replace("Test: divisible by 23", "Test: odd")).unwrap_err()
+        );
+        assert_eq!(
+            ParseError::UnexpectedEnd {
+                expected: "If false: throw to monkey"
+            },
+            parse_notes(&EXAMPLE[..EXAMPLE.len() - 29]).unwrap_err()
+        );
+    }
 }
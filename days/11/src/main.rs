@@ -1,74 +1,140 @@
 use anyhow::Result;
 
+mod cycles;
 mod input;
+mod operation;
+mod simulation;
+mod worry;
 
+use cycles::Strategy;
 use input::Monkey;
+use simulation::{Format, Trace};
+use worry::{Mode, Relief};
 
-fn problem_1(monkeys: &[Monkey]) {
-    const ROUNDS: usize = 20;
+/// How the problems are simulated, from the command line.
+struct Options {
+    /// Representation of the worry levels, the default depends on the problem.
+    mode: Option<Mode>,
+    strategy: Strategy,
 
-    let mut monkeys: Vec<_> = monkeys.to_vec();
-    let mut passes: Vec<_> = std::iter::repeat(0).take(monkeys.len()).collect();
+    /// Relief after every inspection in the second problem, which has none by default. The first
+    /// problem always divides the worry levels by 3, as the puzzle describes.
+    relief: Option<Relief>,
 
-    for _round in 0..ROUNDS {
-        for m_idx in 0..monkeys.len() {
-            let items = std::mem::take(&mut monkeys[m_idx].items);
+    /// Number of rounds of the second problem.
+    rounds: u64,
 
-            for item in items {
-                passes[m_idx] += 1;
-                let worry_level = (monkeys[m_idx].operation)(item) / 3;
-                let target_idx = match worry_level % monkeys[m_idx].modulo_test == 0 {
-                    true => monkeys[m_idx].if_true,
-                    false => monkeys[m_idx].if_false,
-                };
-                monkeys[target_idx].items.push(worry_level);
-            }
-        }
-    }
+    /// Print snapshots of the rounds instead of fast-forwarding.
+    trace: Option<Format>,
 
-    passes.sort();
-    println!("{:?}", passes);
+    /// Print a snapshot every this many rounds while tracing.
+    every: u64,
+}
 
-    let score: i64 = passes.iter().rev().take(2).product();
-    println!("Score: {}", score);
+fn solve(
+    problem: usize,
+    monkeys: &[Monkey],
+    options: &Options,
+    (mode, relief, rounds): (Mode, Relief, u64),
+) {
+    let mode = options.mode.unwrap_or(mode);
+    let result = match options.trace {
+        Some(format) => {
+            let trace = Trace {
+                monkeys,
+                rounds,
+                relief,
+                every: options.every,
+                format,
+            };
+            worry::run_in(mode, monkeys, trace)
+        }
+        None => worry::inspections_in(mode, options.strategy, monkeys, rounds, relief),
+    };
 
+    match result {
+        Ok(passes) => print_passes(&passes),
+        Err(err) => println!("Problem {} failed: {}", problem, err),
+    }
 }
 
-fn problem_2(monkeys: &[Monkey]) {
-    const ROUNDS: usize = 10000;
+fn problem_1(monkeys: &[Monkey], options: &Options) {
+    const ROUNDS: u64 = 20;
 
-    let mut monkeys: Vec<_> = monkeys.to_vec();
-    let mut passes: Vec<_> = std::iter::repeat(0).take(monkeys.len()).collect();
+    solve(
+        1,
+        monkeys,
+        options,
+        (Mode::Checked, Relief::Divide(3), ROUNDS),
+    );
+}
 
-    let lcm: i64 = monkeys.iter().map(|m| m.modulo_test).product();
+fn problem_2(monkeys: &[Monkey], options: &Options) {
+    solve(
+        2,
+        monkeys,
+        options,
+        (
+            Mode::Lcm,
+            options.relief.unwrap_or(Relief::None),
+            options.rounds,
+        ),
+    );
+}
 
-    for _round in 0..ROUNDS {
-        for m_idx in 0..monkeys.len() {
-            let items = std::mem::take(&mut monkeys[m_idx].items);
+fn print_passes(passes: &[u64]) {
+    let mut sorted = passes.to_vec();
+    sorted.sort();
+    println!("{:?}", sorted);
+    println!("Score: {}", worry::monkey_business(passes));
+}
 
-            for item in items {
-                passes[m_idx] += 1;
-                let worry_level = (monkeys[m_idx].operation)(item) % lcm;
-                let target_idx = match worry_level % monkeys[m_idx].modulo_test == 0 {
-                    true => monkeys[m_idx].if_true,
-                    false => monkeys[m_idx].if_false,
-                };
-                monkeys[target_idx].items.push(worry_level);
+fn main() -> Result<()> {
+    let monkeys = input::parse_input("input.txt")?;
+
+    let mut options = Options {
+        mode: None,
+        strategy: Strategy::Cycles,
+        relief: None,
+        rounds: 10000,
+        trace: None,
+        every: 1,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--mode" => options.mode = Some(value()?.parse().map_err(anyhow::Error::msg)?),
+            "--strategy" => options.strategy = value()?.parse().map_err(anyhow::Error::msg)?,
+            "--relief" => {
+                options.relief =
+                    Some(Relief::parse(&value()?, &monkeys).map_err(anyhow::Error::msg)?)
+            }
+            "--rounds" => options.rounds = value()?.parse()?,
+            "--trace" => options.trace = Some(value()?.parse().map_err(anyhow::Error::msg)?),
+            "--every" => {
+                options.every = value()?.parse()?;
+                if options.every == 0 {
+                    anyhow::bail!("--every has to be at least 1");
+                }
+            }
+            "--json" => {
+                println!("{}", serde_json::to_string_pretty(&monkeys)?);
+                return Ok(());
+            }
+            "--notes" => {
+                print!("{}", input::format_notes(&monkeys));
+                return Ok(());
             }
+            x => anyhow::bail!("Unknown option {}", x),
         }
     }
 
-    passes.sort();
-    println!("{:?}", passes);
-
-    let score: i64 = passes.iter().rev().take(2).product();
-    println!("Score: {}", score);
-
-}
-fn main() -> Result<()> {
-    let monkeys = input::get_input();
-    problem_1(&monkeys);
-    problem_2(&monkeys);
+    problem_1(&monkeys, &options);
+    problem_2(&monkeys, &options);
 
     Ok(())
 }
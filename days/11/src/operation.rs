@@ -0,0 +1,275 @@
+use std::{fmt::Display, str::FromStr};
+
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub};
+use serde::{Deserialize, Serialize};
+
+/// Expression for the new worry level of an item, in terms of the `old` one.
+///
+/// Operations are serialised as the text of the expression, e.g. `"old * 19"`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Operation {
+    Old,
+    Const(i64),
+    Add(Box<Operation>, Box<Operation>),
+    Sub(Box<Operation>, Box<Operation>),
+    Mul(Box<Operation>, Box<Operation>),
+
+    /// Integer division, rounding towards zero.
+    Div(Box<Operation>, Box<Operation>),
+}
+
+impl Operation {
+    /// Evaluate the expression for any type of worry level which can hold the constants.
+    /// Returns `None` if the evaluation overflows or divides by zero.
+    pub fn eval<T>(&self, old: &T) -> Option<T>
+    where
+        T: Clone + From<i64> + CheckedAdd + CheckedSub + CheckedMul + CheckedDiv,
+    {
+        match self {
+            Operation::Old => Some(old.clone()),
+            Operation::Const(n) => Some(T::from(*n)),
+            Operation::Add(lhs, rhs) => lhs.eval(old)?.checked_add(&rhs.eval(old)?),
+            Operation::Sub(lhs, rhs) => lhs.eval(old)?.checked_sub(&rhs.eval(old)?),
+            Operation::Mul(lhs, rhs) => lhs.eval(old)?.checked_mul(&rhs.eval(old)?),
+            Operation::Div(lhs, rhs) => lhs.eval(old)?.checked_div(&rhs.eval(old)?),
+        }
+    }
+
+    /// Operator and operands of a binary operation.
+    pub fn binary(&self) -> Option<(char, &Operation, &Operation)> {
+        match self {
+            Operation::Add(lhs, rhs) => Some(('+', lhs, rhs)),
+            Operation::Sub(lhs, rhs) => Some(('-', lhs, rhs)),
+            Operation::Mul(lhs, rhs) => Some(('*', lhs, rhs)),
+            Operation::Div(lhs, rhs) => Some(('/', lhs, rhs)),
+            Operation::Old | Operation::Const(_) => None,
+        }
+    }
+
+    /// How tightly the operation binds its operands, higher is tighter.
+    fn precedence(&self) -> u8 {
+        match self.binary() {
+            Some(('+' | '-', _, _)) => 1,
+            Some(_) => 2,
+            None => 3,
+        }
+    }
+}
+
+impl Display for Operation {
+    /// Write the expression with as few parentheses as possible.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some((op, lhs, rhs)) = self.binary() else {
+            return match self {
+                Operation::Const(n) => write!(f, "{}", n),
+                _ => write!(f, "old"),
+            };
+        };
+
+        // Operators are left associative, so only the right operand needs parentheses if it binds
+        // just as tightly.
+        let prec = self.precedence();
+        if lhs.precedence() < prec {
+            write!(f, "({})", lhs)?;
+        } else {
+            write!(f, "{}", lhs)?;
+        }
+        write!(f, " {} ", op)?;
+        if rhs.precedence() <= prec {
+            write!(f, "({})", rhs)
+        } else {
+            write!(f, "{}", rhs)
+        }
+    }
+}
+
+impl From<Operation> for String {
+    fn from(operation: Operation) -> Self {
+        operation.to_string()
+    }
+}
+
+impl TryFrom<String> for Operation {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Recursive descent parser over the tokens of an expression.
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Self {
+        let mut tokens = Vec::new();
+        let mut rest = s.trim_start();
+        while let Some(c) = rest.chars().next() {
+            let len = if c.is_ascii_alphanumeric() {
+                rest.find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(rest.len())
+            } else {
+                c.len_utf8()
+            };
+            tokens.push(&rest[..len]);
+            rest = rest[len..].trim_start();
+        }
+
+        Parser { tokens, pos: 0 }
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn next_if(&mut self, ops: &[&str]) -> Option<&'a str> {
+        let token = *self.tokens.get(self.pos)?;
+        ops.contains(&token).then(|| {
+            self.pos += 1;
+            token
+        })
+    }
+
+    /// Sums and differences of terms.
+    fn expr(&mut self) -> Result<Operation, String> {
+        let mut lhs = self.term()?;
+        while let Some(op) = self.next_if(&["+", "-"]) {
+            let rhs = Box::new(self.term()?);
+            lhs = match op {
+                "+" => Operation::Add(Box::new(lhs), rhs),
+                _ => Operation::Sub(Box::new(lhs), rhs),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    /// Products and quotients of atoms.
+    fn term(&mut self) -> Result<Operation, String> {
+        let mut lhs = self.atom()?;
+        while let Some(op) = self.next_if(&["*", "/"]) {
+            let rhs = Box::new(self.atom()?);
+            lhs = match op {
+                "*" => Operation::Mul(Box::new(lhs), rhs),
+                _ => Operation::Div(Box::new(lhs), rhs),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn atom(&mut self) -> Result<Operation, String> {
+        match self.next() {
+            Some("old") => Ok(Operation::Old),
+            Some("(") => {
+                let inner = self.expr()?;
+                match self.next() {
+                    Some(")") => Ok(inner),
+                    _ => Err(String::from("Missing closing parenthesis")),
+                }
+            }
+            Some(token) => token
+                .parse()
+                .map(Operation::Const)
+                .map_err(|_| format!("Unexpected '{}'", token)),
+            None => Err(String::from("Unexpected end of expression")),
+        }
+    }
+}
+
+impl FromStr for Operation {
+    type Err = String;
+
+    /// Parse an expression of `old`, non-negative integers, `+`, `-`, `*`, `/` and parentheses,
+    /// with the usual precedence.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let operation = parser.expr()?;
+        match parser.next() {
+            None => Ok(operation),
+            Some(token) => Err(format!("Unexpected '{}'", token)),
+        }
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn parse_puzzle_operations() {
+        assert_eq!(
+            Operation::Mul(Box::new(Operation::Old), Box::new(Operation::Const(19))),
+            "old * 19".parse().unwrap()
+        );
+        assert_eq!(
+            Operation::Mul(Box::new(Operation::Old), Box::new(Operation::Old)),
+            "old * old".parse().unwrap()
+        );
+        assert_eq!(
+            Operation::Add(Box::new(Operation::Old), Box::new(Operation::Const(6))),
+            "old+6".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn precedence_and_parentheses() {
+        let op: Operation = "old + 2 * old - (old - 1) / 3".parse().unwrap();
+        assert_eq!(Some(10 + 20 - 3), op.eval(&10i64));
+        assert_eq!(Some(10 + 20 - 3), op.eval(&10i128));
+        assert_eq!("old + 2 * old - (old - 1) / 3", op.to_string());
+
+        for text in [
+            "(old + 1) * old",
+            "old - (old - 1)",
+            "old / 2 / 3",
+            "old / (2 / 3)",
+        ] {
+            assert_eq!(text, text.parse::<Operation>().unwrap().to_string());
+        }
+        assert_eq!(
+            "old * old",
+            "((old) * (old))".parse::<Operation>().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn eval_checks_arithmetic() {
+        let square: Operation = "old * old".parse().unwrap();
+        assert_eq!(None, square.eval(&i64::MAX));
+        assert_eq!(
+            Some(num_bigint::BigInt::from(i64::MAX) * i64::MAX),
+            square.eval(&num_bigint::BigInt::from(i64::MAX))
+        );
+        assert_eq!(
+            None,
+            "old / (old - 1)".parse::<Operation>().unwrap().eval(&1i64)
+        );
+    }
+
+    #[test]
+    fn invalid_operations() {
+        assert_eq!(
+            Err(String::from("Unexpected 'new'")),
+            "new * 2".parse::<Operation>()
+        );
+        assert_eq!(
+            Err(String::from("Unexpected end of expression")),
+            "old *".parse::<Operation>()
+        );
+        assert_eq!(
+            Err(String::from("Missing closing parenthesis")),
+            "(old + 1".parse::<Operation>()
+        );
+        assert_eq!(
+            Err(String::from("Unexpected '3'")),
+            "old 3".parse::<Operation>()
+        );
+    }
+}
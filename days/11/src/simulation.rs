@@ -0,0 +1,249 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::Serialize;
+
+use crate::{
+    input::Monkey,
+    worry::{self, Arithmetic, Relief, WithArithmetic, WorryError},
+};
+
+/// State of the monkeys after a round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Snapshot {
+    pub round: u64,
+    pub monkeys: Vec<MonkeyState>,
+    pub monkey_business: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MonkeyState {
+    /// Worry levels of the items the monkey holds, as shown by the representation.
+    pub items: Vec<String>,
+
+    /// Number of items the monkey inspected so far.
+    pub inspections: u64,
+}
+
+impl Display for Snapshot {
+    /// Write the snapshot like the puzzle shows the rounds of the example.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "== After round {} ==", self.round)?;
+        for (idx, monkey) in self.monkeys.iter().enumerate() {
+            write!(f, "Monkey {}:", idx)?;
+            if !monkey.items.is_empty() {
+                write!(f, " {}", monkey.items.join(", "))?;
+            }
+            writeln!(f, " (inspected {} items)", monkey.inspections)?;
+        }
+        writeln!(f, "Monkey business: {}", self.monkey_business)
+    }
+}
+
+/// How snapshots are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One JSON object per line.
+    Json,
+    Table,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "table" => Ok(Format::Table),
+            _ => Err(format!("Unknown format '{}', expected json or table", s)),
+        }
+    }
+}
+
+impl Format {
+    pub fn write(&self, snapshot: &Snapshot) -> String {
+        match self {
+            Format::Json => serde_json::to_string(snapshot).unwrap() + "\n",
+            Format::Table => snapshot.to_string(),
+        }
+    }
+}
+
+/// Simulation of the monkeys throwing items around, one round at a time.
+pub struct Simulation<'a, A: Arithmetic> {
+    monkeys: &'a [Monkey],
+    relief: Relief,
+    arithmetic: &'a A,
+
+    /// Items held by each monkey.
+    items: Vec<Vec<A::Value>>,
+    passes: Vec<u64>,
+    round: u64,
+}
+
+impl<'a, A: Arithmetic> Simulation<'a, A> {
+    pub fn new(monkeys: &'a [Monkey], relief: Relief, arithmetic: &'a A) -> Self {
+        Simulation {
+            monkeys,
+            relief,
+            arithmetic,
+            items: monkeys
+                .iter()
+                .map(|m| m.items.iter().map(|&n| arithmetic.value(n)).collect())
+                .collect(),
+            passes: vec![0; monkeys.len()],
+            round: 0,
+        }
+    }
+
+    /// Let every monkey take its turn once.
+    pub fn round(&mut self) -> Result<(), WorryError> {
+        for (m_idx, monkey) in self.monkeys.iter().enumerate() {
+            for item in std::mem::take(&mut self.items[m_idx]) {
+                self.passes[m_idx] += 1;
+                let worry_level = self.arithmetic.apply(&monkey.operation, &item)?;
+                let worry_level = self.relief.apply(self.arithmetic, worry_level)?;
+                let target_idx = match self
+                    .arithmetic
+                    .is_divisible(&worry_level, monkey.modulo_test)?
+                {
+                    true => monkey.if_true,
+                    false => monkey.if_false,
+                };
+                self.items[target_idx].push(worry_level);
+            }
+        }
+        self.round += 1;
+
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            round: self.round,
+            monkeys: self
+                .items
+                .iter()
+                .zip(&self.passes)
+                .map(|(items, &inspections)| MonkeyState {
+                    items: items.iter().map(|item| format!("{:?}", item)).collect(),
+                    inspections,
+                })
+                .collect(),
+            monkey_business: worry::monkey_business(&self.passes),
+        }
+    }
+}
+
+/// Simulate the monkeys for a number of rounds and count how many items each monkey inspects.
+pub fn inspections<A: Arithmetic>(
+    monkeys: &[Monkey],
+    rounds: u64,
+    relief: Relief,
+    arithmetic: &A,
+) -> Result<Vec<u64>, WorryError> {
+    let mut simulation = Simulation::new(monkeys, relief, arithmetic);
+    for _ in 0..rounds {
+        simulation.round()?;
+    }
+
+    Ok(simulation.passes)
+}
+
+/// Simulation which prints a snapshot every few rounds and after the last one.
+pub struct Trace<'a> {
+    pub monkeys: &'a [Monkey],
+    pub rounds: u64,
+    pub relief: Relief,
+    pub every: u64,
+    pub format: Format,
+}
+
+impl WithArithmetic for Trace<'_> {
+    type Output = Vec<u64>;
+
+    fn run<A: Arithmetic>(self, arithmetic: &A) -> Result<Vec<u64>, WorryError> {
+        let mut simulation = Simulation::new(self.monkeys, self.relief, arithmetic);
+        while simulation.round < self.rounds {
+            simulation.round()?;
+            if simulation.round.is_multiple_of(self.every) || simulation.round == self.rounds {
+                print!("{}", self.format.write(&simulation.snapshot()));
+            }
+        }
+
+        Ok(simulation.passes)
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::input::example;
+    #[allow(unused_imports)]
+    use crate::worry::{Checked, Lcm, Residues};
+
+    #[test]
+    fn first_round_of_example() {
+        let monkeys = example();
+        let mut simulation = Simulation::new(&monkeys, Relief::Divide(3), &Checked);
+        simulation.round().unwrap();
+        let snapshot = simulation.snapshot();
+        assert_eq!(
+            "== After round 1 ==\n\
+             Monkey 0: 20, 23, 27, 26 (inspected 2 items)\n\
+             Monkey 1: 2080, 25, 167, 207, 401, 1046 (inspected 4 items)\n\
+             Monkey 2: (inspected 3 items)\n\
+             Monkey 3: (inspected 5 items)\n\
+             Monkey business: 20\n",
+            Format::Table.write(&snapshot)
+        );
+        assert_eq!(
+            "{\"round\":1,\"monkeys\":[{\"items\":[\"20\",\"23\",\"27\",\"26\"],\"inspections\":2},\
+             {\"items\":[\"2080\",\"25\",\"167\",\"207\",\"401\",\"1046\"],\"inspections\":4},\
+             {\"items\":[],\"inspections\":3},{\"items\":[],\"inspections\":5}],\
+             \"monkey_business\":20}\n",
+            Format::Json.write(&snapshot)
+        );
+    }
+
+    #[test]
+    fn relief_policies() {
+        let monkeys = example();
+        let lcm = worry::lcm(&monkeys).unwrap();
+
+        // Without relief the example counts are known after 20 rounds.
+        let passes = inspections(&monkeys, 20, Relief::None, &Lcm { modulus: lcm }).unwrap();
+        assert_eq!(vec![99, 97, 8, 103], passes);
+
+        // Reducing the exact values modulo the LCM, or even a multiple of it, is the same.
+        let relief = Relief::parse("modulo", &monkeys).unwrap();
+        assert_eq!(Relief::Modulo(lcm as i64), relief);
+        assert_eq!(passes, inspections(&monkeys, 20, relief, &Checked).unwrap());
+        let relief = Relief::Modulo(lcm as i64 * 2);
+        assert_eq!(passes, inspections(&monkeys, 20, relief, &Checked).unwrap());
+        let residues = Residues::new(&monkeys);
+        assert_eq!(
+            passes,
+            inspections(&monkeys, 20, relief, &residues).unwrap()
+        );
+
+        // A modulus which loses some of the remainders can't be tracked by a representation
+        // which only keeps remainders.
+        assert_eq!(
+            Err(WorryError::ModuloUnsupported {
+                mode: worry::Mode::Lcm,
+                modulus: 100
+            }),
+            inspections(&monkeys, 20, Relief::Modulo(100), &Lcm { modulus: lcm })
+        );
+        assert_eq!(
+            inspections(&monkeys, 20, Relief::Modulo(23 * 19), &Checked),
+            inspections(&monkeys, 20, Relief::Modulo(23 * 19), &Lcm { modulus: lcm })
+        );
+
+        assert_eq!(Ok(Relief::Divide(3)), Relief::parse("divide:3", &monkeys));
+        assert_eq!(Ok(Relief::None), Relief::parse("none", &monkeys));
+        assert!(Relief::parse("divide:0", &monkeys).is_err());
+        assert!(Relief::parse("sqrt", &monkeys).is_err());
+    }
+}
@@ -0,0 +1,641 @@
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+    str::FromStr,
+};
+
+use num_bigint::BigInt;
+
+use crate::{cycles::Strategy, input::Monkey, operation::Operation};
+
+/// How worry levels are represented during a simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Exact values in an `i128`, failing on overflow.
+    Checked,
+
+    /// Values in an `i128`, reduced modulo the least common multiple of all divisors. This keeps
+    /// every test result intact, but can't be combined with division.
+    Lcm,
+
+    /// Exact values of arbitrary size. Without relief they grow so quickly that only a few rounds
+    /// can be simulated.
+    Big,
+
+    /// The value modulo each monkey's divisor, which is all the tests need. Like [`Mode::Lcm`],
+    /// this can't be combined with division.
+    Residues,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Mode::Checked),
+            "lcm" => Ok(Mode::Lcm),
+            "big" => Ok(Mode::Big),
+            "residues" => Ok(Mode::Residues),
+            _ => Err(format!(
+                "Unknown mode '{}', expected checked, lcm, big or residues",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorryError {
+    /// Applying an operation overflowed or divided by zero.
+    Arithmetic { operation: String, old: String },
+
+    /// The least common multiple of the divisors doesn't fit the representation.
+    LcmOverflow,
+
+    /// The representation only keeps remainders, so it can't divide.
+    DivisionUnsupported { mode: Mode },
+
+    /// The representation only keeps remainders which can't be reduced modulo this number.
+    ModuloUnsupported { mode: Mode, modulus: i64 },
+
+    /// The representation only keeps remainders which don't tell whether a value is divisible by
+    /// this number, e.g. because it isn't the divisor of any of the monkeys.
+    DivisorUnsupported { mode: Mode, divisor: i64 },
+}
+
+impl Display for WorryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorryError::Arithmetic { operation, old } => write!(
+                f,
+                "new = {} overflows or divides by zero for old = {}",
+                operation, old
+            ),
+            WorryError::LcmOverflow => {
+                write!(f, "the least common multiple of the divisors overflows")
+            }
+            WorryError::DivisionUnsupported { mode } => {
+                write!(f, "worry levels in {:?} mode can't be divided", mode)
+            }
+            WorryError::ModuloUnsupported { mode, modulus } => write!(
+                f,
+                "worry levels in {:?} mode can't be reduced modulo {}",
+                mode, modulus
+            ),
+            WorryError::DivisorUnsupported { mode, divisor } => write!(
+                f,
+                "worry levels in {:?} mode can't be tested for divisibility by {}",
+                mode, divisor
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WorryError {}
+
+/// Arithmetic on worry levels in one of the representations.
+pub trait Arithmetic {
+    type Value: Clone + Eq + Hash + Debug;
+
+    fn value(&self, n: i64) -> Self::Value;
+
+    fn apply(&self, operation: &Operation, old: &Self::Value) -> Result<Self::Value, WorryError>;
+
+    /// Divide by a positive number, rounding down, to model relief.
+    fn divide(&self, value: &Self::Value, divisor: i64) -> Result<Self::Value, WorryError>;
+
+    /// Reduce modulo a positive number, to model relief.
+    fn reduce(&self, value: &Self::Value, modulus: i64) -> Result<Self::Value, WorryError>;
+
+    /// Whether the value is divisible by the divisor of one of the monkeys.
+    fn is_divisible(&self, value: &Self::Value, divisor: i64) -> Result<bool, WorryError>;
+
+    /// Whether there are only finitely many values, whatever the operations do to them.
+    fn is_bounded(&self) -> bool;
+}
+
+fn arithmetic_error(operation: &Operation, old: &impl Display) -> WorryError {
+    WorryError::Arithmetic {
+        operation: operation.to_string(),
+        old: old.to_string(),
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Least common multiple of the divisors of all monkeys.
+pub fn lcm(monkeys: &[Monkey]) -> Result<i128, WorryError> {
+    monkeys.iter().try_fold(1i128, |lcm, monkey| {
+        let divisor = monkey.modulo_test as i128;
+        (lcm / gcd(lcm, divisor))
+            .checked_mul(divisor)
+            .ok_or(WorryError::LcmOverflow)
+    })
+}
+
+pub struct Checked;
+
+impl Arithmetic for Checked {
+    type Value = i128;
+
+    fn value(&self, n: i64) -> i128 {
+        n as i128
+    }
+
+    fn apply(&self, operation: &Operation, old: &i128) -> Result<i128, WorryError> {
+        operation
+            .eval(old)
+            .ok_or_else(|| arithmetic_error(operation, old))
+    }
+
+    fn divide(&self, value: &i128, divisor: i64) -> Result<i128, WorryError> {
+        Ok(value.div_euclid(divisor as i128))
+    }
+
+    fn reduce(&self, value: &i128, modulus: i64) -> Result<i128, WorryError> {
+        Ok(value.rem_euclid(modulus as i128))
+    }
+
+    fn is_divisible(&self, value: &i128, divisor: i64) -> Result<bool, WorryError> {
+        Ok(value % divisor as i128 == 0)
+    }
+
+    fn is_bounded(&self) -> bool {
+        false
+    }
+}
+
+pub struct Lcm {
+    pub modulus: i128,
+}
+
+impl Arithmetic for Lcm {
+    type Value = i128;
+
+    fn value(&self, n: i64) -> i128 {
+        (n as i128).rem_euclid(self.modulus)
+    }
+
+    /// Values stay below the modulus, so as long as its square fits an `i128` this never
+    /// overflows for operations without nested products.
+    fn apply(&self, operation: &Operation, old: &i128) -> Result<i128, WorryError> {
+        if has_division(operation) {
+            return Err(WorryError::DivisionUnsupported { mode: Mode::Lcm });
+        }
+        operation
+            .eval(old)
+            .map(|new: i128| new.rem_euclid(self.modulus))
+            .ok_or_else(|| arithmetic_error(operation, old))
+    }
+
+    fn divide(&self, _: &i128, _: i64) -> Result<i128, WorryError> {
+        Err(WorryError::DivisionUnsupported { mode: Mode::Lcm })
+    }
+
+    /// Values are already reduced modulo the LCM, which is enough for any multiple of it. For a
+    /// divisor of it the value modulo that divisor still has the right remainder for the LCM.
+    fn reduce(&self, value: &i128, modulus: i64) -> Result<i128, WorryError> {
+        let modulus = modulus as i128;
+        if modulus % self.modulus == 0 {
+            Ok(*value)
+        } else if self.modulus % modulus == 0 {
+            Ok(value % modulus)
+        } else {
+            Err(WorryError::ModuloUnsupported {
+                mode: Mode::Lcm,
+                modulus: modulus as i64,
+            })
+        }
+    }
+
+    /// Only divisors of the LCM keep their remainders.
+    fn is_divisible(&self, value: &i128, divisor: i64) -> Result<bool, WorryError> {
+        match self.modulus % divisor as i128 == 0 {
+            true => Ok(value % divisor as i128 == 0),
+            false => Err(WorryError::DivisorUnsupported {
+                mode: Mode::Lcm,
+                divisor,
+            }),
+        }
+    }
+
+    fn is_bounded(&self) -> bool {
+        true
+    }
+}
+
+pub struct Big;
+
+impl Arithmetic for Big {
+    type Value = BigInt;
+
+    fn value(&self, n: i64) -> BigInt {
+        BigInt::from(n)
+    }
+
+    fn apply(&self, operation: &Operation, old: &BigInt) -> Result<BigInt, WorryError> {
+        operation
+            .eval(old)
+            .ok_or_else(|| arithmetic_error(operation, old))
+    }
+
+    fn divide(&self, value: &BigInt, divisor: i64) -> Result<BigInt, WorryError> {
+        // Round down like the other representations, not towards zero.
+        let divisor = BigInt::from(divisor);
+        let quotient = value / &divisor;
+        if value.sign() == num_bigint::Sign::Minus && &quotient * &divisor != *value {
+            Ok(quotient - 1)
+        } else {
+            Ok(quotient)
+        }
+    }
+
+    fn reduce(&self, value: &BigInt, modulus: i64) -> Result<BigInt, WorryError> {
+        let modulus = BigInt::from(modulus);
+        Ok(((value % &modulus) + &modulus) % &modulus)
+    }
+
+    fn is_divisible(&self, value: &BigInt, divisor: i64) -> Result<bool, WorryError> {
+        Ok((value % divisor) == BigInt::ZERO)
+    }
+
+    fn is_bounded(&self) -> bool {
+        false
+    }
+}
+
+pub struct Residues {
+    /// The distinct divisors, in the order of the residues.
+    pub divisors: Vec<i64>,
+}
+
+impl Residues {
+    pub fn new(monkeys: &[Monkey]) -> Self {
+        let mut divisors: Vec<i64> = monkeys.iter().map(|m| m.modulo_test).collect();
+        divisors.sort();
+        divisors.dedup();
+        Residues { divisors }
+    }
+
+    fn eval(&self, operation: &Operation, old: &[i64]) -> Result<Vec<i64>, WorryError> {
+        let Some((op, lhs, rhs)) = operation.binary() else {
+            return Ok(match operation {
+                Operation::Const(n) => self.value(*n),
+                _ => old.to_vec(),
+            });
+        };
+        let (lhs, rhs) = (self.eval(lhs, old)?, self.eval(rhs, old)?);
+
+        Ok(self
+            .divisors
+            .iter()
+            .zip(lhs.iter().zip(&rhs))
+            .map(|(&d, (&a, &b))| {
+                let (a, b, d) = (a as i128, b as i128, d as i128);
+                let result = match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    _ => a * b,
+                };
+                result.rem_euclid(d) as i64
+            })
+            .collect())
+    }
+}
+
+impl Arithmetic for Residues {
+    type Value = Vec<i64>;
+
+    fn value(&self, n: i64) -> Vec<i64> {
+        self.divisors.iter().map(|d| n.rem_euclid(*d)).collect()
+    }
+
+    fn apply(&self, operation: &Operation, old: &Vec<i64>) -> Result<Vec<i64>, WorryError> {
+        if has_division(operation) {
+            return Err(WorryError::DivisionUnsupported {
+                mode: Mode::Residues,
+            });
+        }
+        self.eval(operation, old)
+    }
+
+    fn divide(&self, _: &Vec<i64>, _: i64) -> Result<Vec<i64>, WorryError> {
+        Err(WorryError::DivisionUnsupported {
+            mode: Mode::Residues,
+        })
+    }
+
+    /// Reducing modulo a common multiple of all divisors keeps every residue.
+    fn reduce(&self, value: &Vec<i64>, modulus: i64) -> Result<Vec<i64>, WorryError> {
+        match self.divisors.iter().all(|d| modulus % d == 0) {
+            true => Ok(value.clone()),
+            false => Err(WorryError::ModuloUnsupported {
+                mode: Mode::Residues,
+                modulus,
+            }),
+        }
+    }
+
+    /// Only the divisors the residues were computed for can be tested.
+    fn is_divisible(&self, value: &Vec<i64>, divisor: i64) -> Result<bool, WorryError> {
+        match self.divisors.binary_search(&divisor) {
+            Ok(idx) => Ok(value[idx] == 0),
+            Err(_) => Err(WorryError::DivisorUnsupported {
+                mode: Mode::Residues,
+                divisor,
+            }),
+        }
+    }
+
+    fn is_bounded(&self) -> bool {
+        true
+    }
+}
+
+fn has_division(operation: &Operation) -> bool {
+    match operation {
+        Operation::Div(_, _) => true,
+        _ => operation
+            .binary()
+            .is_some_and(|(_, lhs, rhs)| has_division(lhs) || has_division(rhs)),
+    }
+}
+
+/// What happens to the worry level of an item after a monkey inspects it and gets bored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relief {
+    /// The worry level stays as it is.
+    None,
+
+    /// The worry level is divided by a positive number, rounding down.
+    Divide(i64),
+
+    /// The worry level is reduced modulo a positive number.
+    Modulo(i64),
+}
+
+impl Relief {
+    /// Parse `none`, `divide:K`, `modulo:K` or `modulo`, which is short for the least common
+    /// multiple of the divisors of the monkeys.
+    pub fn parse(s: &str, monkeys: &[Monkey]) -> Result<Self, String> {
+        let number = |n: &str| match n.parse() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!(
+                "Invalid relief '{}', expected a positive number",
+                n
+            )),
+        };
+
+        match s.split_once(':') {
+            None if s == "none" => Ok(Relief::None),
+            None if s == "modulo" => {
+                let modulus = lcm(monkeys).map_err(|err| err.to_string())?;
+                i64::try_from(modulus)
+                    .map(Relief::Modulo)
+                    .map_err(|_| WorryError::LcmOverflow.to_string())
+            }
+            Some(("divide", k)) => Ok(Relief::Divide(number(k)?)),
+            Some(("modulo", k)) => Ok(Relief::Modulo(number(k)?)),
+            _ => Err(format!(
+                "Unknown relief '{}', expected none, divide:K, modulo:K or modulo",
+                s
+            )),
+        }
+    }
+
+    pub fn apply<A: Arithmetic>(
+        &self,
+        arithmetic: &A,
+        value: A::Value,
+    ) -> Result<A::Value, WorryError> {
+        match *self {
+            Relief::None => Ok(value),
+            Relief::Divide(divisor) => arithmetic.divide(&value, divisor),
+            Relief::Modulo(modulus) => arithmetic.reduce(&value, modulus),
+        }
+    }
+}
+
+/// Something to run with the arithmetic of whichever mode is chosen at run time.
+pub trait WithArithmetic {
+    type Output;
+
+    fn run<A: Arithmetic>(self, arithmetic: &A) -> Result<Self::Output, WorryError>;
+}
+
+/// Run with the arithmetic of the given mode.
+pub fn run_in<R: WithArithmetic>(
+    mode: Mode,
+    monkeys: &[Monkey],
+    runner: R,
+) -> Result<R::Output, WorryError> {
+    match mode {
+        Mode::Checked => runner.run(&Checked),
+        Mode::Lcm => {
+            let modulus = lcm(monkeys)?;
+            runner.run(&Lcm { modulus })
+        }
+        Mode::Big => runner.run(&Big),
+        Mode::Residues => runner.run(&Residues::new(monkeys)),
+    }
+}
+
+/// Count the inspections with the arithmetic of the given mode, running the rounds with the
+/// given strategy.
+pub fn inspections_in(
+    mode: Mode,
+    strategy: Strategy,
+    monkeys: &[Monkey],
+    rounds: u64,
+    relief: Relief,
+) -> Result<Vec<u64>, WorryError> {
+    struct Inspections<'a> {
+        strategy: Strategy,
+        monkeys: &'a [Monkey],
+        rounds: u64,
+        relief: Relief,
+    }
+
+    impl WithArithmetic for Inspections<'_> {
+        type Output = Vec<u64>;
+
+        fn run<A: Arithmetic>(self, arithmetic: &A) -> Result<Vec<u64>, WorryError> {
+            let Inspections {
+                strategy,
+                monkeys,
+                rounds,
+                relief,
+            } = self;
+            match strategy {
+                Strategy::BruteForce => {
+                    crate::simulation::inspections(monkeys, rounds, relief, arithmetic)
+                }
+                Strategy::Cycles => crate::cycles::inspections(monkeys, rounds, relief, arithmetic),
+            }
+        }
+    }
+
+    let inspections = Inspections {
+        strategy,
+        monkeys,
+        rounds,
+        relief,
+    };
+    run_in(mode, monkeys, inspections)
+}
+
+/// Product of the two highest inspection counts. Over enough rounds it doesn't fit a `u64`.
+pub fn monkey_business(passes: &[u64]) -> u128 {
+    let mut passes = passes.to_vec();
+    passes.sort();
+    passes.iter().rev().take(2).map(|&n| n as u128).product()
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::input::example;
+
+    #[test]
+    fn real_lcm() {
+        let mut monkeys = example();
+        assert_eq!(Ok(23 * 19 * 13 * 17), lcm(&monkeys));
+        monkeys[0].modulo_test = 26;
+        assert_eq!(Ok(26 * 19 * 17), lcm(&monkeys));
+    }
+
+    #[test]
+    fn problem_1_example() {
+        let monkeys = example();
+        for mode in [Mode::Checked, Mode::Big] {
+            let passes =
+                inspections_in(mode, Strategy::BruteForce, &monkeys, 20, Relief::Divide(3))
+                    .unwrap();
+            assert_eq!(vec![101, 95, 7, 105], passes);
+            assert_eq!(10605, monkey_business(&passes));
+        }
+        assert_eq!(
+            Err(WorryError::DivisionUnsupported {
+                mode: Mode::Residues
+            }),
+            inspections_in(
+                Mode::Residues,
+                Strategy::BruteForce,
+                &monkeys,
+                20,
+                Relief::Divide(3)
+            )
+        );
+    }
+
+    #[test]
+    fn problem_2_example() {
+        let monkeys = example();
+        for mode in [Mode::Lcm, Mode::Residues] {
+            let passes =
+                inspections_in(mode, Strategy::BruteForce, &monkeys, 10000, Relief::None).unwrap();
+            assert_eq!(2713310158, monkey_business(&passes));
+        }
+
+        // The exact worry levels get too large to track after a few rounds.
+        let error = inspections_in(
+            Mode::Checked,
+            Strategy::BruteForce,
+            &monkeys,
+            10000,
+            Relief::None,
+        )
+        .unwrap_err();
+        assert!(matches!(error, WorryError::Arithmetic { .. }));
+    }
+
+    #[test]
+    fn representations_agree() {
+        let monkeys = example();
+        let expected =
+            inspections_in(Mode::Big, Strategy::BruteForce, &monkeys, 12, Relief::None).unwrap();
+        for mode in [Mode::Lcm, Mode::Residues] {
+            assert_eq!(
+                expected,
+                inspections_in(mode, Strategy::BruteForce, &monkeys, 12, Relief::None).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn subtraction_and_division() {
+        let mut monkeys = example();
+        monkeys[1].operation = "old - 60".parse().unwrap();
+        let expected = inspections_in(
+            Mode::Big,
+            Strategy::BruteForce,
+            &monkeys,
+            20,
+            Relief::Divide(3),
+        )
+        .unwrap();
+        assert_eq!(
+            expected,
+            inspections_in(
+                Mode::Checked,
+                Strategy::BruteForce,
+                &monkeys,
+                20,
+                Relief::Divide(3)
+            )
+            .unwrap()
+        );
+        let expected =
+            inspections_in(Mode::Big, Strategy::BruteForce, &monkeys, 10, Relief::None).unwrap();
+        assert_eq!(
+            expected,
+            inspections_in(
+                Mode::Residues,
+                Strategy::BruteForce,
+                &monkeys,
+                10,
+                Relief::None
+            )
+            .unwrap()
+        );
+
+        monkeys[3].operation = "old / 2 + 3".parse().unwrap();
+        assert_eq!(
+            Err(WorryError::DivisionUnsupported { mode: Mode::Lcm }),
+            inspections_in(Mode::Lcm, Strategy::BruteForce, &monkeys, 10, Relief::None)
+        );
+    }
+
+    #[test]
+    fn unknown_divisor() {
+        let monkeys = example();
+        let residues = Residues::new(&monkeys);
+        assert_eq!(Ok(true), residues.is_divisible(&residues.value(46), 23));
+        assert_eq!(
+            Err(WorryError::DivisorUnsupported {
+                mode: Mode::Residues,
+                divisor: 7
+            }),
+            residues.is_divisible(&residues.value(14), 7)
+        );
+
+        let lcm = Lcm {
+            modulus: lcm(&monkeys).unwrap(),
+        };
+        assert_eq!(Ok(false), lcm.is_divisible(&lcm.value(46), 13));
+        assert_eq!(
+            Err(WorryError::DivisorUnsupported {
+                mode: Mode::Lcm,
+                divisor: 7
+            }),
+            lcm.is_divisible(&lcm.value(14), 7)
+        );
+    }
+}
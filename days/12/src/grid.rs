@@ -0,0 +1,142 @@
+use anyhow::Result;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Position {
+    pub fn add(&self, dr: isize, dc: isize, rows: usize, cols: usize) -> Option<Self> {
+        let row = self.row as isize + dr;
+        let col = self.col as isize + dc;
+
+        if row < 0 || col < 0 || row as usize >= rows || col as usize >= cols {
+            None
+        } else {
+            Some(Position {
+                row: row as usize,
+                col: col as usize,
+            })
+        }
+    }
+
+    pub fn manhattan(&self, other: Position) -> usize {
+        self.row.abs_diff(other.row) + self.col.abs_diff(other.col)
+    }
+}
+
+/// Height map of the terrain, with elevations from `b'a'` to `b'z'`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    heights: Vec<Vec<u8>>,
+}
+
+impl Grid {
+    /// Parse the height map, which marks the start with `S` at elevation `a` and the end with
+    /// `E` at elevation `z`. Returns the grid with the start and end positions.
+    pub fn parse(text: &str) -> Result<(Grid, Position, Position)> {
+        let mut heights: Vec<Vec<u8>> = text
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.as_bytes().to_vec())
+            .collect();
+        if heights.is_empty() || heights.iter().any(|row| row.len() != heights[0].len()) {
+            anyhow::bail!("The height map has to be a non-empty rectangle");
+        }
+
+        let mut find = |marker: u8, height: u8| -> Result<Position> {
+            for (row, line) in heights.iter_mut().enumerate() {
+                if let Some(col) = line.iter().position(|&h| h == marker) {
+                    line[col] = height;
+                    return Ok(Position { row, col });
+                }
+            }
+            anyhow::bail!("No '{}' in the height map", marker as char)
+        };
+        let start = find(b'S', b'a')?;
+        let end = find(b'E', b'z')?;
+
+        let grid = Grid { heights };
+        if let Some(pos) = grid
+            .positions()
+            .find(|&pos| !grid.height(pos).is_ascii_lowercase())
+        {
+            anyhow::bail!(
+                "Unknown elevation '{}' at row {}, column {}",
+                grid.height(pos) as char,
+                pos.row + 1,
+                pos.col + 1
+            );
+        }
+
+        Ok((grid, start, end))
+    }
+
+    pub fn rows(&self) -> usize {
+        self.heights.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.heights[0].len()
+    }
+
+    pub fn height(&self, pos: Position) -> u8 {
+        self.heights[pos.row][pos.col]
+    }
+
+    /// All positions, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.rows()).flat_map(move |row| (0..self.cols()).map(move |col| Position { row, col }))
+    }
+
+    /// The positions left, right, above and below, as far as they are on the grid.
+    pub fn neighbours(&self, pos: Position) -> impl Iterator<Item = Position> + '_ {
+        [(0, -1), (0, 1), (-1, 0), (1, 0)]
+            .into_iter()
+            .filter_map(move |(dr, dc)| pos.add(dr, dc, self.rows(), self.cols()))
+    }
+
+    /// Draw a path the way the puzzle does, with an arrow on every position showing where the
+    /// path goes next, `E` where it ends and `.` everywhere else.
+    pub fn render_path(&self, path: &[Position]) -> String {
+        let mut cells = vec![vec!['.'; self.cols()]; self.rows()];
+        for step in path.windows(2) {
+            let (from, to) = (step[0], step[1]);
+            cells[from.row][from.col] = match (to.row.cmp(&from.row), to.col.cmp(&from.col)) {
+                (std::cmp::Ordering::Less, _) => '^',
+                (std::cmp::Ordering::Greater, _) => 'v',
+                (_, std::cmp::Ordering::Less) => '<',
+                _ => '>',
+            };
+        }
+        if let Some(end) = path.last() {
+            cells[end.row][end.col] = 'E';
+        }
+
+        cells
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn parse_markers() {
+        let (grid, start, end) = Grid::parse("Sbc\nabE\n").unwrap();
+        assert_eq!((2, 3), (grid.rows(), grid.cols()));
+        assert_eq!(Position { row: 0, col: 0 }, start);
+        assert_eq!(Position { row: 1, col: 2 }, end);
+        assert_eq!((b'a', b'z'), (grid.height(start), grid.height(end)));
+        assert_eq!(3, grid.neighbours(Position { row: 0, col: 1 }).count());
+
+        assert!(Grid::parse("Sbc\nab\n").is_err());
+        assert!(Grid::parse("Sbc\nabc\n").is_err());
+        assert!(Grid::parse("SbE\na1c\n").is_err());
+    }
+}
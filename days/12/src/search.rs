@@ -0,0 +1,257 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    str::FromStr,
+};
+
+use crate::grid::{Grid, Position};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Fewest steps, ignoring the cost of the steps.
+    Bfs,
+
+    /// Lowest cost.
+    Dijkstra,
+
+    /// Lowest cost, guided by the Manhattan distance to the nearest goal. This assumes every
+    /// step costs at least 1, otherwise the path found may not be the cheapest.
+    AStar,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bfs" => Ok(Algorithm::Bfs),
+            "dijkstra" => Ok(Algorithm::Dijkstra),
+            "astar" => Ok(Algorithm::AStar),
+            _ => Err(format!(
+                "Unknown algorithm '{}', expected bfs, dijkstra or astar",
+                s
+            )),
+        }
+    }
+}
+
+/// Path found by a search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    /// Every position on the path, from the start to the goal.
+    pub positions: Vec<Position>,
+
+    /// Total cost of the steps.
+    pub cost: usize,
+
+    /// Number of positions the search expanded before it reached the goal.
+    pub expanded: usize,
+}
+
+impl Path {
+    pub fn steps(&self) -> usize {
+        self.positions.len() - 1
+    }
+}
+
+/// Search for a path over a grid, moving left, right, up or down.
+pub struct Search<'a, M, C, G> {
+    pub grid: &'a Grid,
+
+    /// Whether a step from the first position to the second one is allowed.
+    pub can_move: M,
+
+    /// Cost of a step from the first position to the second one.
+    pub cost: C,
+
+    /// Whether the search is done once it reaches the position.
+    pub goal: G,
+}
+
+impl<M, C, G> Search<'_, M, C, G>
+where
+    M: Fn(Position, Position) -> bool,
+    C: Fn(Position, Position) -> usize,
+    G: Fn(Position) -> bool,
+{
+    /// Find a path from the start to the nearest goal, or `None` if no goal can be reached.
+    pub fn run(&self, start: Position, algorithm: Algorithm) -> Option<Path> {
+        match algorithm {
+            Algorithm::Bfs => self.bfs(start),
+            Algorithm::Dijkstra => self.best_first(start, |_| 0),
+            Algorithm::AStar => {
+                let goals: Vec<Position> =
+                    self.grid.positions().filter(|&p| (self.goal)(p)).collect();
+                self.best_first(start, |pos| {
+                    goals
+                        .iter()
+                        .map(|&goal| pos.manhattan(goal))
+                        .min()
+                        .unwrap_or(0)
+                })
+            }
+        }
+    }
+
+    /// Breadth-first search, which reaches every position in as few steps as possible.
+    fn bfs(&self, start: Position) -> Option<Path> {
+        let mut previous = vec![vec![None; self.grid.cols()]; self.grid.rows()];
+        let mut seen = vec![vec![false; self.grid.cols()]; self.grid.rows()];
+        let mut queue = VecDeque::from([start]);
+        seen[start.row][start.col] = true;
+
+        let mut expanded = 0;
+        while let Some(position) = queue.pop_front() {
+            expanded += 1;
+            if (self.goal)(position) {
+                return Some(self.path(&previous, position, expanded));
+            }
+
+            for next in self.grid.neighbours(position) {
+                if !seen[next.row][next.col] && (self.can_move)(position, next) {
+                    seen[next.row][next.col] = true;
+                    previous[next.row][next.col] = Some(position);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Expand the positions in order of their cost so far plus the estimate of the remaining
+    /// cost, which is Dijkstra's algorithm without an estimate and A* with one.
+    fn best_first(&self, start: Position, estimate: impl Fn(Position) -> usize) -> Option<Path> {
+        let mut previous = vec![vec![None; self.grid.cols()]; self.grid.rows()];
+        let mut dist = vec![vec![usize::MAX; self.grid.cols()]; self.grid.rows()];
+        let mut queue = BinaryHeap::from([Reverse((estimate(start), 0, start))]);
+        dist[start.row][start.col] = 0;
+
+        let mut expanded = 0;
+        while let Some(Reverse((_, cost, position))) = queue.pop() {
+            // Skip positions which were queued again with a lower cost.
+            if cost > dist[position.row][position.col] {
+                continue;
+            }
+            expanded += 1;
+            if (self.goal)(position) {
+                return Some(self.path(&previous, position, expanded));
+            }
+
+            for next in self.grid.neighbours(position) {
+                if !(self.can_move)(position, next) {
+                    continue;
+                }
+                let next_cost = cost + (self.cost)(position, next);
+                if next_cost < dist[next.row][next.col] {
+                    dist[next.row][next.col] = next_cost;
+                    previous[next.row][next.col] = Some(position);
+                    queue.push(Reverse((next_cost + estimate(next), next_cost, next)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Follow the previous positions back from the end to the start.
+    fn path(&self, previous: &[Vec<Option<Position>>], end: Position, expanded: usize) -> Path {
+        let mut positions = vec![end];
+        while let Some(prev) =
+            previous[positions.last().unwrap().row][positions.last().unwrap().col]
+        {
+            positions.push(prev);
+        }
+        positions.reverse();
+
+        let cost = positions
+            .windows(2)
+            .map(|step| (self.cost)(step[0], step[1]))
+            .sum();
+
+        Path {
+            positions,
+            cost,
+            expanded,
+        }
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[allow(dead_code)]
+    const EXAMPLE: &str = "Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi\n";
+
+    #[allow(dead_code)]
+    const ALGORITHMS: [Algorithm; 3] = [Algorithm::Bfs, Algorithm::Dijkstra, Algorithm::AStar];
+
+    #[test]
+    fn hiking_trail() {
+        let (grid, start, end) = Grid::parse(EXAMPLE).unwrap();
+        let climb = |from: Position, to: Position| grid.height(to) <= grid.height(from) + 1;
+
+        for algorithm in ALGORITHMS {
+            let search = Search {
+                grid: &grid,
+                can_move: climb,
+                cost: |_, _| 1,
+                goal: |pos| pos == end,
+            };
+            let path = search.run(start, algorithm).unwrap();
+            assert_eq!(31, path.steps(), "{:?}", algorithm);
+            assert_eq!(31, path.cost);
+            assert_eq!((start, end), (path.positions[0], path.positions[31]));
+            assert!(path
+                .positions
+                .windows(2)
+                .all(|step| step[0].manhattan(step[1]) == 1 && climb(step[0], step[1])));
+
+            // Walk down from the end to the nearest lowest position.
+            let search = Search {
+                grid: &grid,
+                can_move: |from, to| climb(to, from),
+                cost: |_, _| 1,
+                goal: |pos| grid.height(pos) == b'a',
+            };
+            assert_eq!(29, search.run(end, algorithm).unwrap().steps());
+        }
+    }
+
+    #[test]
+    fn weighted_steps() {
+        let (grid, start, end) = Grid::parse(EXAMPLE).unwrap();
+        // Anywhere within reach, but climbing is expensive.
+        let search = Search {
+            grid: &grid,
+            can_move: |_, _| true,
+            cost: |from: Position, to: Position| {
+                1 + 10 * grid.height(to).saturating_sub(grid.height(from)) as usize
+            },
+            goal: |pos| pos == end,
+        };
+
+        let fewest_steps = search.run(start, Algorithm::Bfs).unwrap();
+        let dijkstra = search.run(start, Algorithm::Dijkstra).unwrap();
+        let astar = search.run(start, Algorithm::AStar).unwrap();
+        assert_eq!(7, fewest_steps.steps());
+        assert!(dijkstra.cost < fewest_steps.cost);
+        assert_eq!(dijkstra.cost, astar.cost);
+        assert!(astar.expanded <= dijkstra.expanded);
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let (grid, start, _) = Grid::parse("SazE\n").unwrap();
+        for algorithm in ALGORITHMS {
+            let search = Search {
+                grid: &grid,
+                can_move: |from: Position, to: Position| grid.height(to) <= grid.height(from) + 1,
+                cost: |_, _| 1,
+                goal: |pos: Position| pos.col == 3,
+            };
+            assert_eq!(None, search.run(start, algorithm));
+        }
+    }
+}